@@ -0,0 +1,300 @@
+use crate::components::{board_size, BasicCell, Board, GameChangedEvent, CELL_COLOR, CELL_SIZE};
+use crate::core::{Change, Game};
+use crate::playback::Playback;
+use crate::AppState;
+
+use bevy::prelude::*;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// the time between one wave of a flood and the next
+const WAVE_STEP: f64 = 0.03;
+/// big floods speed their waves up to finish within this many seconds
+const MAX_WAVE_TIME: f64 = 0.6;
+/// the time between one mine showing and the next once the game is lost
+const CHAIN_STEP: f64 = 0.08;
+const MAX_CHAIN_TIME: f64 = 1.5;
+const CONFETTI: usize = 80;
+const CONFETTI_COLORS: [Color; 6] = [
+    Color::RED,
+    Color::GOLD,
+    Color::LIME_GREEN,
+    Color::CYAN,
+    Color::FUCHSIA,
+    Color::ORANGE,
+];
+/// seconds a piece of confetti falls for before it is gone
+const CONFETTI_LIFETIME: f32 = 2.5;
+const GRAVITY: f32 = 400.0;
+
+/// Lets the cells catch up with the board over a moment rather than all at once. the game is
+/// already in its final state underneath, so this only changes what is shown
+pub struct Animations {
+    pub enabled: bool,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Animations { enabled: true }
+    }
+}
+
+/// hides a cell as it was before the last move until it is its turn to be shown
+#[derive(Component)]
+pub struct Cover {
+    /// seconds since startup
+    reveal_at: f64,
+}
+
+/// thrown up over the board when the game is won
+#[derive(Component)]
+pub struct Confetti {
+    age: f32,
+    /// radians a second
+    spin: f32,
+    velocity: Vec2,
+}
+
+/// how many steps each opened cell is from where the opening started, walking only through the
+/// cells opened along with it. a chord can open several regions that don't touch, and each of them
+/// starts its own wave
+fn wave_distances(game: &Game, opened: &[(usize, usize)]) -> HashMap<(usize, usize), u32> {
+    let opened_set: HashSet<(usize, usize)> = opened.iter().copied().collect();
+    let mut distances = HashMap::new();
+    for start in opened {
+        if distances.contains_key(start) {
+            continue;
+        }
+        distances.insert(*start, 0);
+        let mut queue = VecDeque::from([(*start, 0)]);
+        while let Some((position, distance)) = queue.pop_front() {
+            for neighbour in game.neighbours(position) {
+                if opened_set.contains(&neighbour) && !distances.contains_key(&neighbour) {
+                    distances.insert(neighbour, distance + 1);
+                    queue.push_back((neighbour, distance + 1));
+                }
+            }
+        }
+    }
+    distances
+}
+
+fn spawn_cover(commands: &mut Commands, basic_cell: &BasicCell, reveal_at: f64) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: CELL_COLOR,
+                custom_size: Some(Vec2::new(CELL_SIZE, CELL_SIZE)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(basic_cell.position.truncate().extend(1.0)),
+            ..Default::default()
+        })
+        .insert(Cover { reveal_at });
+}
+
+/// covers the cells a flood opened and lets them show in waves going out from the click
+fn animate_flood(
+    commands: &mut Commands,
+    board: &Board,
+    cell_query: &Query<&BasicCell>,
+    opened: &[(usize, usize)],
+    now: f64,
+) {
+    let distances = wave_distances(&board.game, opened);
+    let deepest = distances.values().copied().max().unwrap_or(0);
+    if deepest == 0 {
+        return;
+    }
+    let step = WAVE_STEP.min(MAX_WAVE_TIME / deepest as f64);
+    for basic_cell in cell_query.iter() {
+        if let Some(distance) = distances.get(&(basic_cell.row, basic_cell.column)) {
+            if *distance > 0 {
+                spawn_cover(commands, basic_cell, now + *distance as f64 * step);
+            }
+        }
+    }
+}
+
+/// covers the mines shown on a loss and lets them show one after another, starting with the
+/// ones closest to the mine that went off
+fn animate_loss(
+    commands: &mut Commands,
+    board: &Board,
+    cell_query: &Query<&BasicCell>,
+    exploded: (usize, usize),
+    now: f64,
+) {
+    let distance = |basic_cell: &BasicCell| {
+        let rows = basic_cell.row as f32 - exploded.0 as f32;
+        let columns = basic_cell.column as f32 - exploded.1 as f32;
+        rows * rows + columns * columns
+    };
+    let mut mines: Vec<&BasicCell> = cell_query
+        .iter()
+        .filter(|basic_cell| {
+            let cell = &board.game.cells[basic_cell.row][basic_cell.column];
+            cell.mine && !cell.opened && !cell.flagged()
+        })
+        .collect();
+    if mines.is_empty() {
+        return;
+    }
+    mines.sort_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap());
+    let step = CHAIN_STEP.min(MAX_CHAIN_TIME / mines.len() as f64);
+    for (index, basic_cell) in mines.into_iter().enumerate() {
+        spawn_cover(commands, basic_cell, now + (index + 1) as f64 * step);
+    }
+}
+
+/// throws confetti up from the top of the board
+fn animate_win(commands: &mut Commands, board: &Board) {
+    let size = board_size(board.game.height, board.game.width);
+    let mut rng = SmallRng::from_entropy();
+    for _ in 0..CONFETTI {
+        let x = rng.gen_range(-size.x / 2.0..=size.x / 2.0);
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: CONFETTI_COLORS[rng.gen_range(0..CONFETTI_COLORS.len())],
+                    custom_size: Some(Vec2::new(6.0, 10.0)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(x, size.y / 2.0, 4.0),
+                ..Default::default()
+            })
+            .insert(Confetti {
+                age: 0.0,
+                spin: rng.gen_range(-6.0..=6.0),
+                velocity: Vec2::new(rng.gen_range(-80.0..=80.0), rng.gen_range(100.0..=300.0)),
+            });
+    }
+}
+
+/// starts the animations for the changes made to the game. anything that takes cells back, like
+/// undoing a move or dealing a new board, finishes whatever is still playing first. replays are
+/// shown as they happen
+#[allow(clippy::too_many_arguments)]
+pub fn animate_changes(
+    animations: Res<Animations>,
+    board_query: Query<&Board>,
+    new_board_query: Query<&Board, Added<Board>>,
+    cell_query: Query<&BasicCell>,
+    mut commands: Commands,
+    mut ev_changed: EventReader<GameChangedEvent>,
+    playing_query: Query<Entity, Or<(With<Cover>, With<Confetti>)>>,
+    playback: Res<Playback>,
+    time: Res<Time>,
+) {
+    let changes: Vec<Change> = ev_changed
+        .iter()
+        .map(|GameChangedEvent(change)| *change)
+        .collect();
+    let taken_back = changes
+        .iter()
+        .any(|change| matches!(change, Change::Closed(_) | Change::Resumed));
+    if taken_back || new_board_query.iter().next().is_some() {
+        for entity in playing_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if changes.is_empty() || !animations.enabled || playback.is_active() {
+        return;
+    }
+
+    let board = board_query.single();
+    let now = time.seconds_since_startup();
+    // the mine that went off is shown straight away
+    let opened: Vec<(usize, usize)> = changes
+        .iter()
+        .filter_map(|change| match change {
+            Change::Opened((row, col)) if !board.game.cells[*row][*col].mine => Some((*row, *col)),
+            _ => None,
+        })
+        .collect();
+    animate_flood(&mut commands, board, &cell_query, &opened, now);
+    for change in changes {
+        match change {
+            Change::Lost(position) => {
+                animate_loss(&mut commands, board, &cell_query, position, now)
+            }
+            Change::Won => animate_win(&mut commands, board),
+            _ => {}
+        }
+    }
+}
+
+pub fn uncover_cells(
+    mut commands: Commands,
+    cover_query: Query<(Entity, &Cover)>,
+    time: Res<Time>,
+) {
+    let now = time.seconds_since_startup();
+    for (entity, cover) in cover_query.iter() {
+        if cover.reveal_at <= now {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn fall_confetti(
+    mut commands: Commands,
+    mut confetti_query: Query<(Entity, &mut Confetti, &mut Transform)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut confetti, mut transform) in confetti_query.iter_mut() {
+        confetti.age += delta;
+        if confetti.age > CONFETTI_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        confetti.velocity.y -= GRAVITY * delta;
+        transform.translation += (confetti.velocity * delta).extend(0.0);
+        transform.rotate(Quat::from_rotation_z(confetti.spin * delta));
+    }
+}
+
+/// any click while something is still playing finishes it. the click is used up, so it doesn't
+/// also open or flag the cell under it
+pub fn skip_animations(
+    mut commands: Commands,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    playing_query: Query<Entity, Or<(With<Cover>, With<Confetti>)>>,
+) {
+    if playing_query.iter().next().is_none() {
+        return;
+    }
+    let mut skipped = false;
+    for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+        if mouse_button_input.just_pressed(button) {
+            mouse_button_input.reset(button);
+            skipped = true;
+        }
+    }
+    if !skipped {
+        return;
+    }
+    for entity in playing_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Animations>();
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(
+                    skip_animations
+                        .label("skip_animations")
+                        .before("both_buttons"),
+                )
+                .with_system(animate_changes.after("apply_game_changes"))
+                .with_system(uncover_cells)
+                .with_system(fall_confetti),
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Plays seeded games with the bot on every difficulty and reports how it did, for comparing
+//! guessing strategies and catching changes to how boards are generated.
+//!
+//! cargo run --release --bin benchmark -- [--games N] [--seed N] [--strategy random|safest]
+//! [--no-guess]
+use minesweeper::core::bot::{self, Strategy};
+use minesweeper::core::{Game, Generation};
+use minesweeper::GameConfig;
+
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+struct Options {
+    games: u64,
+    generation: Generation,
+    seed: u64,
+    strategies: Vec<Strategy>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options {
+            games: 1000,
+            generation: Generation::Random,
+            seed: 0,
+            strategies: vec![Strategy::Random, Strategy::Safest],
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--games" => options.games = value()?.parse()?,
+                "--seed" => options.seed = value()?.parse()?,
+                "--strategy" => {
+                    options.strategies = match value()?.as_str() {
+                        "random" => vec![Strategy::Random],
+                        "safest" => vec![Strategy::Safest],
+                        strategy => return Err(format!("unknown strategy {}", strategy).into()),
+                    }
+                }
+                "--no-guess" => options.generation = Generation::no_guess(),
+                _ => return Err(format!("unknown argument {}", arg).into()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// the totals over every game of one difficulty played with one strategy
+#[derive(Default)]
+struct Totals {
+    games: u64,
+    guesses: u64,
+    /// the 3BV and time of the games that were won, since a lost game stops part way through
+    won_elapsed: Duration,
+    won_three_bv: u64,
+    wins: u64,
+}
+
+impl Totals {
+    fn report(&self, name: &str, strategy: Strategy) {
+        let games = self.games.max(1) as f64;
+        println!(
+            "{:<13} {:<7} {:>6.1}% {:>12.0} {:>8.2}",
+            name,
+            format!("{:?}", strategy).to_lowercase(),
+            100.0 * self.wins as f64 / games,
+            self.won_three_bv as f64 / self.won_elapsed.as_secs_f64().max(f64::EPSILON),
+            self.guesses as f64 / games,
+        );
+    }
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "{} games per difficulty from seed {}",
+        options.games, options.seed
+    );
+    println!(
+        "{:<13} {:<7} {:>7} {:>12} {:>8}",
+        "difficulty", "guess", "wins", "3bv/s", "guesses"
+    );
+    let difficulties = [
+        ("beginner", GameConfig::BEGINNER),
+        ("intermediate", GameConfig::INTERMEDIATE),
+        ("expert", GameConfig::EXPERT),
+    ];
+    for (name, config) in difficulties {
+        for strategy in &options.strategies {
+            let mut totals = Totals::default();
+            // every strategy plays the same boards
+            for seed in options.seed..options.seed + options.games {
+                let mut game = Game::with_seed(config.rows, config.columns, config.mines, seed)
+                    .with_generation(options.generation);
+                let start = Instant::now();
+                let outcome = match bot::play(&mut game, *strategy) {
+                    Ok(outcome) => outcome,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        std::process::exit(1);
+                    }
+                };
+                let elapsed = start.elapsed();
+
+                totals.games += 1;
+                totals.guesses += outcome.guesses as u64;
+                if outcome.won {
+                    totals.wins += 1;
+                    totals.won_elapsed += elapsed;
+                    totals.won_three_bv += outcome.three_bv as u64;
+                }
+            }
+            totals.report(name, *strategy);
+        }
+    }
+}
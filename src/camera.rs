@@ -0,0 +1,64 @@
+use crate::components::{play_area, Board};
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+/// cells never get drawn more than this many times their size on small boards
+const MAX_ZOOM: f32 = 2.0;
+
+/// the camera the board is drawn with, as opposed to the one for the ui
+#[derive(Component)]
+pub struct MainCamera;
+
+/// converts a position measured in pixels from the bottom left of the window, like the cursor or
+/// a touch, into the coordinates the sprites are placed in
+pub fn window_to_world(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    position: Vec2,
+) -> Vec2 {
+    let window_size = Vec2::new(window.width(), window.height());
+    let ndc = (position / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix.inverse();
+    ndc_to_world.project_point3(ndc.extend(-1.0)).truncate()
+}
+
+/// zooms the camera so the whole board fits in the window. this runs every frame rather than on
+/// `WindowResized` since starting a game on a different sized board needs it too
+pub fn fit_camera(
+    board_query: Query<&Board>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    windows: Res<Windows>,
+) {
+    let window = if let Some(window) = windows.get_primary() {
+        window
+    } else {
+        return;
+    };
+    let board = if let Some(board) = board_query.iter().next() {
+        board
+    } else {
+        return;
+    };
+
+    let area = play_area(board.game.height, board.game.width);
+    let scale = (area.x / window.width())
+        .max(area.y / window.height())
+        .max(1.0 / MAX_ZOOM);
+
+    for mut projection in camera_query.iter_mut() {
+        // only writing when it changes keeps bevy from rebuilding the projection every frame
+        if (projection.scale - scale).abs() > f32::EPSILON {
+            projection.scale = scale;
+        }
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(fit_camera);
+    }
+}
@@ -1,328 +1,689 @@
-use super::cell::{ApplyMaterialEvent, BasicCell, Cell, NewCell, CELL_COLOR, SURROUND};
-use crate::AppState;
+use super::cell::{ApplyMaterialEvent, BasicCell, NewCell, CELL_COLOR};
+use crate::core::{Change, Clicks, Game, GameStats, History, Mark};
+use crate::leaderboard::{unix_time, Difficulty, Entry, Leaderboard};
+use crate::menu::FONT;
+use crate::playback::Playback;
+use crate::replay::{Action, Replay};
+use crate::save::SavedGame;
+use crate::statistics::Statistics;
+use crate::storage::PlatformStorage;
+use crate::{AppState, GameConfig};
 
 use bevy::prelude::*;
-use rand::{rngs::SmallRng, Rng, SeedableRng};
-use std::convert::TryFrom;
-use std::error::Error;
-
-pub struct ClearOpenCellsEvent(pub (usize, usize));
-pub struct ChordSolvedCellEvent(pub (usize, usize));
-pub struct FlagSolvedCellEvent(pub (usize, usize));
+use std::time::Duration;
+
+/// a move for the board to make, from the player or a replay. every kind of move goes through this
+/// one event so that the moves of a frame are made and recorded in the order they were asked for
+pub struct ActionEvent(pub Action);
+/// an action the board went through with, in the order they were made. an undo or redo that
+/// wasn't allowed isn't sent
+pub struct ActionAppliedEvent(pub Action);
+pub struct GameChangedEvent(pub Change);
 pub struct MineClickedEvent;
 pub struct AllCellsOpenedEvent;
-
+/// throws away the current board and deals a new one
+pub struct NewGameEvent;
+/// replaces the current board with a game that was saved earlier or imported
+pub struct LoadGameEvent(pub SavedGame);
+/// replaces the current board with the start of a replay, which is then played back on it
+pub struct PlayReplayEvent(pub Replay);
+
+/// the win or lose sprite and the stats under it, shown once the game is over
 #[derive(Component)]
-pub struct Board {
-    pub cells: Vec<Vec<Cell>>,
-    pub cells_unopened: usize,
-    pub game_over: bool,
-    pub height: usize,
-    pub initialized: bool,
-    pub width: usize,
+pub struct ResultSprite;
+
+/// whether the point, in world coordinates, lands on the win or lose sprite
+pub fn result_contains(
+    images: &Assets<Image>,
+    result_query: &Query<(&Transform, &Handle<Image>), With<ResultSprite>>,
+    position: Vec2,
+) -> bool {
+    result_query.iter().any(|(transform, texture)| {
+        let size = if let Some(image) = images.get(texture) {
+            image.size() * transform.scale.truncate()
+        } else {
+            return false;
+        };
+        let offset = (position - transform.translation.truncate()).abs();
+        offset.x <= size.x / 2.0 && offset.y <= size.y / 2.0
+    })
 }
 
-impl Board {
-    pub fn check_in_bounds(
-        &self,
-        (cell_row, cell_col): (usize, usize),
-        (row, col): (isize, isize),
-    ) -> Option<(usize, usize)> {
-        let r = cell_row as isize + row;
-        let c = cell_col as isize + col;
-
-        if (0..self.height as isize).contains(&r) && (0..self.width as isize).contains(&c) {
-            return Some((r as usize, c as usize));
-        }
-        return None;
-    }
+/// the border drawn around the board
+#[derive(Component)]
+pub struct Wall;
 
-    pub fn fill_board(&mut self, mines: u16, start: (usize, usize)) -> Result<(), Box<dyn Error>> {
-        let board_size = self.height * self.width;
+/// a cell showing a mine, or a flag that was wrong, because the game was lost. they go back to
+/// how they were if the loss is undone
+#[derive(Component)]
+pub struct LossReveal;
+
+/// the covered cells that turn out to be mines once the game is lost
+const REVEALED_MINE_COLOR: Color = Color::SILVER;
+const WRONG_FLAG_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+pub const CELL_SIZE: f32 = 22.0;
+pub const CELL_SPACING: f32 = 2.0;
+
+/// the gap between the board and the middle of the walls around it
+const WALL_PADDING: f32 = 9.0;
+/// room above and below the walls for the result face and the flag mode toggle
+const PLAY_AREA_MARGIN: f32 = 180.0;
+
+/// the size in pixels of a board with the given dimensions, from the outer edge of the first cell
+/// to the outer edge of the last
+pub fn board_size(rows: usize, columns: usize) -> Vec2 {
+    Vec2::new(
+        columns as f32 * (CELL_SIZE + CELL_SPACING) - CELL_SPACING,
+        rows as f32 * (CELL_SIZE + CELL_SPACING) - CELL_SPACING,
+    )
+}
 
-        if mines as usize >= board_size {
-            return Err("You have requested too many mines for this size of board".into());
-        }
+/// the distance between the middles of opposite walls
+fn wall_bounds(rows: usize, columns: usize) -> Vec2 {
+    board_size(rows, columns) + Vec2::new(WALL_PADDING, WALL_PADDING) * 2.0
+}
 
-        self.cells_unopened = board_size - mines as usize;
-        self.initialized = true;
+/// everything that has to stay on screen for a board: the walls, the result face above them and
+/// the flag mode toggle below
+pub fn play_area(rows: usize, columns: usize) -> Vec2 {
+    wall_bounds(rows, columns) + Vec2::new(40.0, PLAY_AREA_MARGIN * 2.0)
+}
 
-        let rows = self.height as usize;
-        let columns = self.width as usize;
-        let mut curr_mines = 0;
+#[derive(Component)]
+pub struct Board {
+    /// only the clicks made while the game was being played
+    pub clicks: Clicks,
+    /// the outcome has gone into the lifetime statistics, so taking back a loss and playing on
+    /// doesn't count the game twice
+    pub counted: bool,
+    /// time spent playing, from the first reveal until the game is won or lost. only counts
+    /// while the game is on screen, so it stops while paused
+    pub elapsed: Duration,
+    pub game: Game,
+    pub history: History,
+    /// the board was built from a layout, so it isn't counted for the leaderboard or the
+    /// statistics
+    pub imported: bool,
+    /// right clicks put a question mark between a flag and a blank cell
+    pub question_marks: bool,
+    /// moves can't be undone
+    pub ranked: bool,
+}
 
-        let mut rng = SmallRng::from_entropy();
+impl Board {
+    /// mines minus flags placed, which goes negative when too many cells are flagged
+    pub fn mines_left(&self) -> i32 {
+        let flags = self
+            .game
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.flagged())
+            .count();
+        self.game.mines as i32 - flags as i32
+    }
 
-        while curr_mines < mines {
-            let row = rng.gen_range(0..rows);
-            let col = rng.gen_range(0..columns);
-            let cell = &mut self.cells[row][col];
-            if row == start.0 && col == start.1 || cell.surrounds((start.0, start.1)) {
-                continue;
-            }
+    pub fn stats(&self) -> GameStats {
+        GameStats::new(&self.game, self.clicks, self.elapsed)
+    }
+}
 
-            if !cell.mine {
-                cell.mine = true;
-                curr_mines += 1;
-            }
-        }
+pub fn tick_timer(time: Res<Time>, mut board_query: Query<&mut Board>) {
+    let mut board = board_query.single_mut();
+    if board.game.initialized && !board.game.is_over() {
+        board.elapsed += time.delta();
+    }
+}
 
-        (0..rows).for_each(|y| {
-            (0..columns).for_each(|x| {
-                let value = SURROUND.iter().fold(0, |acc, (surround_x, surround_y)| {
-                    let surround_x = x as isize + surround_x;
-                    let surround_y = y as isize + surround_y;
-
-                    if (0..rows).contains(&usize::try_from(surround_y).unwrap_or(usize::MAX))
-                        && (0..columns).contains(&usize::try_from(surround_x).unwrap_or(usize::MAX))
-                        && self.cells[surround_y as usize][surround_x as usize].mine
-                    {
-                        return acc + 1;
-                    }
-                    return acc;
-                });
-                self.cells[y][x].value = value;
-            })
-        });
-        Ok(())
+fn record_win(board: &Board, leaderboard: &mut Leaderboard, storage: &mut PlatformStorage) {
+    let stats = board.stats();
+    let entry = Entry {
+        clicks: stats.clicks.total(),
+        date: unix_time(),
+        three_bv: stats.three_bv,
+        time: board.elapsed,
+        undos: board.history.undos,
+    };
+    if entry.undos > 0 {
+        info!("won after undoing {} moves", entry.undos);
+    }
+    if let Some(place) = leaderboard.record(Difficulty::from(&board.game), entry) {
+        info!("new best time, number {} on the leaderboard", place + 1);
+        if let Err(err) = leaderboard.save(storage) {
+            warn!("could not save the leaderboard: {}", err);
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn game_over(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    board_query: Query<&Board>,
     mut ev_mine_clicked: EventReader<MineClickedEvent>,
     mut ev_all_opened: EventReader<AllCellsOpenedEvent>,
-    mut board_query: Query<&mut Board>,
+    mut leaderboard: ResMut<Leaderboard>,
+    playback: Res<Playback>,
+    mut storage: ResMut<PlatformStorage>,
 ) {
+    let board = board_query.single();
     let mat = if ev_mine_clicked.iter().next().is_some() {
         asset_server.load("u_lose.png")
     } else if ev_all_opened.iter().next().is_some() {
+        // replays are watched rather than played, so they don't count
+        if !playback.is_active() && !board.imported {
+            record_win(board, &mut leaderboard, &mut storage);
+        }
         asset_server.load("u_win.png")
     } else {
         return;
     };
 
-    board_query.single_mut().game_over = true;
-
-    let mut transform = Transform::from_xyz(0.0, 250.0, 1.0);
+    let bounds = wall_bounds(board.game.height, board.game.width);
+    let mut transform = Transform::from_xyz(0.0, bounds.y / 2.0 + 90.0, 1.0);
     transform.apply_non_uniform_scale(Vec3::new(3.0, 3.0, 3.0));
-    commands.spawn_bundle(SpriteBundle {
-        texture: mat.into(),
-        transform,
-        ..Default::default()
-    });
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: mat.into(),
+            transform,
+            ..Default::default()
+        })
+        .insert(ResultSprite);
+
+    let stats = board.stats();
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                format!(
+                    "3BV {}/{}  3BV/s {:.2}\n{} clicks  {:.0}% efficiency",
+                    stats.solved_three_bv,
+                    stats.three_bv,
+                    stats.three_bv_per_second(),
+                    stats.clicks.total(),
+                    stats.efficiency() * 100.0
+                ),
+                TextStyle {
+                    font: asset_server.load(FONT),
+                    font_size: 16.0,
+                    color: Color::MIDNIGHT_BLUE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform::from_xyz(0.0, bounds.y / 2.0 + 35.0, 1.0),
+            ..Default::default()
+        })
+        .insert(ResultSprite);
 }
 
-pub fn clear_open_cells(
+/// adds the first outcome of every game that is played, rather than watched, to the lifetime
+/// statistics. boards built from a layout aren't counted
+pub fn record_outcome(
     mut board_query: Query<&mut Board>,
-    mut cell_query: Query<&BasicCell>,
-    mut ev_apply_material: EventWriter<ApplyMaterialEvent>,
-    mut ev_open_cells: EventReader<ClearOpenCellsEvent>,
-    mut ev_all_opened: EventWriter<AllCellsOpenedEvent>,
+    mut ev_mine_clicked: EventReader<MineClickedEvent>,
+    mut ev_all_opened: EventReader<AllCellsOpenedEvent>,
+    playback: Res<Playback>,
+    mut statistics: ResMut<Statistics>,
+    mut storage: ResMut<PlatformStorage>,
 ) {
     let mut board = board_query.single_mut();
-
-    let mut queue: Vec<(usize, usize)> = ev_open_cells
-        .iter()
-        .map(|ClearOpenCellsEvent((row, col))| (*row, *col))
-        .collect();
-    while queue.len() > 0 {
-        let (curr_row, curr_col) = queue.pop().unwrap();
-        SURROUND.iter().for_each(|(surround_row, surround_col)| {
-            let (valid_row, valid_col) = if let Some((valid_row, valid_col)) =
-                board.check_in_bounds((curr_row, curr_col), (*surround_row, *surround_col))
-            {
-                (valid_row, valid_col)
-            } else {
-                return;
-            };
-
-            let cell = &mut board.cells[valid_row][valid_col];
-            if cell.opened || cell.mine || cell.flagged {
-                return;
-            }
-            if cell.value == 0 {
-                queue.push((valid_row, valid_col));
-            }
-
-            cell.opened = true;
-            board.cells_unopened -= 1;
-            for basic_cell in cell_query.iter_mut() {
-                if basic_cell.row == valid_row && basic_cell.column == valid_col {
-                    ev_apply_material.send(ApplyMaterialEvent((valid_row, valid_col)));
-                    return;
-                }
-            }
-        });
+    let lost = ev_mine_clicked.iter().count() > 0;
+    let won = ev_all_opened.iter().count() > 0;
+    if !(lost || won) || board.counted || board.imported || playback.is_active() {
+        return;
     }
 
-    if board.cells_unopened == 0 {
-        ev_all_opened.send(AllCellsOpenedEvent);
+    board.counted = true;
+    let record = statistics.record(Difficulty::from(&board.game));
+    if won {
+        record.win(board.elapsed);
+    } else {
+        record.lose();
+    }
+    if let Err(err) = statistics.save(&mut *storage) {
+        warn!("could not save the statistics: {}", err);
     }
 }
 
-pub fn chord_solved_cell(
+/// makes the moves one after another in the order they were sent, counting the clicks made while
+/// the game is being played
+pub fn apply_actions(
     mut board_query: Query<&mut Board>,
-    mut ev_chord_cell: EventReader<ChordSolvedCellEvent>,
-    mut ev_open_cells: EventWriter<ClearOpenCellsEvent>,
+    mut ev_action: EventReader<ActionEvent>,
+    mut ev_applied: EventWriter<ActionAppliedEvent>,
+    mut ev_changed: EventWriter<GameChangedEvent>,
 ) {
     let mut board = board_query.single_mut();
-
-    if let Some(ChordSolvedCellEvent((row, col))) = ev_chord_cell.iter().next() {
-        let (row, col) = (*row, *col);
-        let chord_cell = &mut board.cells[row][col];
-        let mut mines_left = chord_cell.value;
-        SURROUND.iter().for_each(|(surround_row, surround_col)| {
-            let (valid_row, valid_col) = if let Some((valid_row, valid_col)) =
-                board.check_in_bounds((row, col), (*surround_row, *surround_col))
-            {
-                (valid_row, valid_col)
-            } else {
-                return;
-            };
-
-            let cell = &mut board.cells[valid_row][valid_col];
-            if cell.opened {
-                return;
+    let board = &mut *board;
+
+    for ActionEvent(action) in ev_action.iter() {
+        let playing = !board.game.is_over();
+        let applied = action.apply(
+            &mut board.game,
+            &mut board.history,
+            board.question_marks,
+            board.ranked,
+        );
+        let changes = match applied {
+            Ok(Some(changes)) => changes,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("could not make the move: {}", err);
+                continue;
             }
-
-            if cell.mine && cell.flagged {
-                mines_left -= 1;
+        };
+        if playing {
+            match action {
+                Action::Reveal(_) => board.clicks.left += 1,
+                Action::Chord(_) => board.clicks.chord += 1,
+                Action::AutoFlag(_) | Action::ToggleFlag(_) => board.clicks.right += 1,
+                Action::Undo | Action::Redo => {}
             }
-        });
+        }
+        ev_applied.send(ActionAppliedEvent(*action));
+        for change in changes {
+            ev_changed.send(GameChangedEvent(change));
+        }
+    }
+}
+
+fn cell_sprite(asset_server: &AssetServer, texture: &str) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(CELL_SIZE, CELL_SIZE)),
+            ..Default::default()
+        },
+        texture: asset_server.load(texture),
+        ..Default::default()
+    }
+}
+
+/// gives a covered cell the look of its mark
+fn show_mark(
+    asset_server: &AssetServer,
+    commands: &mut Commands,
+    entity: Entity,
+    sprite: &mut Sprite,
+    mark: Mark,
+) {
+    let child = match mark {
+        Mark::Blank => {
+            sprite.color = CELL_COLOR;
+            return;
+        }
+        Mark::Flag => {
+            sprite.color = Color::WHITE;
+            commands
+                .spawn_bundle(cell_sprite(asset_server, "flag.png"))
+                .id()
+        }
+        Mark::Question => {
+            sprite.color = CELL_COLOR;
+            commands
+                .spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        "?",
+                        TextStyle {
+                            font: asset_server.load(FONT),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    ..Default::default()
+                })
+                .id()
+        }
+    };
+    commands.entity(entity).push_children(&[child]);
+}
 
-        if mines_left == 0 {
-            ev_open_cells.send(ClearOpenCellsEvent((row, col)))
+/// shows where every mine was and crosses out the flags that weren't on one
+fn reveal_loss(
+    asset_server: &AssetServer,
+    board: &Board,
+    cell_query: &mut Query<(&BasicCell, Entity, &mut Sprite)>,
+    commands: &mut Commands,
+) {
+    for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
+        let cell = &board.game.cells[basic_cell.row][basic_cell.column];
+        if cell.opened || cell.mine == cell.flagged() {
+            continue;
+        }
+
+        commands.entity(entity).despawn_descendants();
+        let mine = commands
+            .spawn_bundle(cell_sprite(asset_server, "mine.png"))
+            .id();
+        commands
+            .entity(entity)
+            .push_children(&[mine])
+            .insert(LossReveal);
+        sprite.color = REVEALED_MINE_COLOR;
+        if cell.mine {
+            continue;
+        }
+        for angle in [1.0, -1.0] {
+            let stroke = commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: WRONG_FLAG_COLOR,
+                        custom_size: Some(Vec2::new(CELL_SIZE, 3.0)),
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, 1.0),
+                        rotation: Quat::from_rotation_z(angle * std::f32::consts::FRAC_PI_4),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .id();
+            commands.entity(entity).push_children(&[stroke]);
         }
     }
 }
 
-pub fn flag_solved_cell(
+/// turns the changes made to the game into sprites and the game over events
+#[allow(clippy::too_many_arguments)]
+pub fn apply_game_changes(
     asset_server: Res<AssetServer>,
-    mut board_query: Query<&mut Board>,
+    board_query: Query<&Board>,
     mut cell_query: Query<(&BasicCell, Entity, &mut Sprite)>,
     mut commands: Commands,
-    mut ev_flag_cell: EventReader<FlagSolvedCellEvent>,
+    mut ev_all_opened: EventWriter<AllCellsOpenedEvent>,
+    mut ev_apply_material: EventWriter<ApplyMaterialEvent>,
+    mut ev_changed: EventReader<GameChangedEvent>,
+    mut ev_mine_clicked: EventWriter<MineClickedEvent>,
+    reveal_query: Query<Entity, With<LossReveal>>,
+    result_query: Query<Entity, With<ResultSprite>>,
 ) {
-    let mut board = board_query.single_mut();
+    let board = board_query.single();
+    for GameChangedEvent(change) in ev_changed.iter() {
+        match *change {
+            Change::Opened(position) => ev_apply_material.send(ApplyMaterialEvent(position)),
+            Change::Flagged((row, col)) | Change::Questioned((row, col)) => {
+                for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
+                    if !(row == basic_cell.row && col == basic_cell.column) {
+                        continue;
+                    }
+                    let mark = board.game.cells[row][col].mark;
+                    show_mark(&asset_server, &mut commands, entity, &mut sprite, mark);
+                }
+            }
+            Change::Unflagged((row, col))
+            | Change::Unquestioned((row, col))
+            | Change::Closed((row, col)) => {
+                for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
+                    if !(row == basic_cell.row && col == basic_cell.column) {
+                        continue;
+                    }
+                    sprite.color = CELL_COLOR;
+                    commands.entity(entity).despawn_descendants();
+                }
+            }
+            Change::Lost(_) => {
+                reveal_loss(&asset_server, board, &mut cell_query, &mut commands);
+                ev_mine_clicked.send(MineClickedEvent);
+            }
+            Change::Won => ev_all_opened.send(AllCellsOpenedEvent),
+            Change::Resumed => {
+                for entity in result_query.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
+                    if !reveal_query.contains(entity) {
+                        continue;
+                    }
+                    commands.entity(entity).despawn_descendants();
+                    commands.entity(entity).remove::<LossReveal>();
+                    let mark = board.game.cells[basic_cell.row][basic_cell.column].mark;
+                    show_mark(&asset_server, &mut commands, entity, &mut sprite, mark);
+                }
+            }
+        }
+    }
+}
 
-    let (row, col) = if let Some(FlagSolvedCellEvent((row, col))) = ev_flag_cell.iter().next() {
-        (*row, *col)
-    } else {
+/// keeps the game up to date in storage after every move so it can be continued later
+pub fn autosave(
+    board_query: Query<&Board>,
+    mut ev_changed: EventReader<GameChangedEvent>,
+    playback: Res<Playback>,
+    mut storage: ResMut<PlatformStorage>,
+) {
+    // a replay would overwrite the game the player left unfinished
+    if ev_changed.iter().count() == 0 || playback.is_active() {
         return;
-    };
+    }
 
-    let flag_cell = &board.cells[row][col];
-    let unopened_cells = SURROUND.into_iter().fold(
-        vec![],
-        |mut unopened_cells, (surround_row, surround_col)| {
-            let (valid_row, valid_col) = if let Some((valid_row, valid_col)) =
-                board.check_in_bounds((row, col), (surround_row, surround_col))
-            {
-                (valid_row, valid_col)
-            } else {
-                return unopened_cells;
-            };
-
-            let cell = &board.cells[valid_row][valid_col];
-            if cell.opened {
-                return unopened_cells;
-            }
-            unopened_cells.push((valid_row, valid_col));
-            unopened_cells
-        },
-    );
+    let board = board_query.single();
+    let result = if board.game.is_over() {
+        SavedGame::clear(&mut *storage)
+    } else {
+        SavedGame {
+            clicks: board.clicks,
+            elapsed: board.elapsed,
+            game: board.game.clone(),
+            imported: board.imported,
+            ranked: board.ranked,
+            undos: board.history.undos,
+        }
+        .save(&mut *storage)
+    };
+    if let Err(err) = result {
+        warn!("could not save the game: {}", err);
+    }
+}
 
-    if unopened_cells.len() != flag_cell.value as usize {
+/// gives the cells of a board that was just spawned the look of its game, which is only needed
+/// for loaded games since new ones start out closed
+pub fn restore_cells(
+    board_query: Query<&Board, Added<Board>>,
+    mut ev_apply_material: EventWriter<ApplyMaterialEvent>,
+    mut ev_changed: EventWriter<GameChangedEvent>,
+) {
+    let board = if let Some(board) = board_query.iter().next() {
+        board
+    } else {
         return;
+    };
+
+    for cell in board.game.cells.iter().flatten() {
+        let position = (cell.row, cell.column);
+        if cell.opened {
+            ev_apply_material.send(ApplyMaterialEvent(position));
+        } else if cell.mark == Mark::Flag {
+            ev_changed.send(GameChangedEvent(Change::Flagged(position)));
+        } else if cell.mark == Mark::Question {
+            ev_changed.send(GameChangedEvent(Change::Questioned(position)));
+        }
     }
-    unopened_cells.into_iter().for_each(|(row, col)| {
-        for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
-            if !(row == basic_cell.row && col == basic_cell.column) {
-                continue;
-            }
-            let cell = &mut board.cells[row][col];
-
-            if !cell.flagged {
-                cell.flagged = true;
-                let child = commands
-                    .spawn_bundle(SpriteBundle {
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(22.0, 22.0)),
-                            ..Default::default()
-                        },
+}
 
-                        texture: asset_server.load("flag.png"),
-                        ..Default::default()
-                    })
-                    .id();
+pub fn generate_board(mut commands: Commands, config: Res<GameConfig>) {
+    spawn_board(&mut commands, config_board(&config));
+}
 
-                commands.entity(entity).push_children(&[child]);
-                sprite.color = Color::WHITE;
-            }
+/// deals a new board for any of the events, preferring a replay and then a loaded game when more
+/// than one was sent
+#[allow(clippy::too_many_arguments)]
+pub fn new_game(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    board_query: Query<Entity, With<Board>>,
+    cell_query: Query<Entity, With<BasicCell>>,
+    result_query: Query<Entity, With<ResultSprite>>,
+    wall_query: Query<Entity, With<Wall>>,
+    mut ev_load_game: EventReader<LoadGameEvent>,
+    mut ev_new_game: EventReader<NewGameEvent>,
+    mut ev_play_replay: EventReader<PlayReplayEvent>,
+    mut storage: ResMut<PlatformStorage>,
+) {
+    let new_game = ev_new_game.iter().count() > 0;
+    let loaded = ev_load_game.iter().last();
+    let board = if let Some(PlayReplayEvent(replay)) = ev_play_replay.iter().last() {
+        info!("playing back a replay with seed {}", replay.seed);
+        Board {
+            clicks: Clicks::default(),
+            counted: false,
+            elapsed: Duration::ZERO,
+            game: replay.game(),
+            history: History::default(),
+            imported: false,
+            question_marks: replay.question_marks,
+            ranked: replay.ranked,
         }
-    })
+    } else if let Some(LoadGameEvent(saved)) = loaded {
+        Board {
+            clicks: saved.clicks,
+            counted: false,
+            elapsed: saved.elapsed,
+            game: saved.game.clone(),
+            history: History {
+                undos: saved.undos,
+                ..Default::default()
+            },
+            imported: saved.imported,
+            question_marks: config.question_marks,
+            ranked: saved.ranked,
+        }
+    } else if new_game {
+        // starting over gives up on the saved game
+        if let Err(err) = SavedGame::clear(&mut *storage) {
+            warn!("could not clear the saved game: {}", err);
+        }
+        config_board(&config)
+    } else {
+        return;
+    };
+
+    for entity in board_query
+        .iter()
+        .chain(cell_query.iter())
+        .chain(result_query.iter())
+        .chain(wall_query.iter())
+    {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_board(&mut commands, board);
 }
 
-pub fn generate_board(mut commands: Commands) {
-    let height = 16usize;
-    let width = 16usize;
-    let spacing = 2.0;
-    let size = Vec2::new(22.0, 22.0);
-    let mine_width = width as f32 * (size.x + spacing) - spacing;
-    let offset = Vec3::new(
-        -(mine_width - size.x) / 2.0,
-        -(mine_width - size.y) / 2.0,
-        0.0,
-    );
-
-    let cells: Vec<Vec<Cell>> = (0..height)
-        .map(|row| {
-            (0..width)
-                .map(|column| {
-                    let position = Vec3::new(
-                        column as f32 * (size.x + spacing),
-                        row as f32 * (size.y + spacing),
-                        0.0,
-                    ) + offset;
-                    commands
-                        .spawn_bundle(SpriteBundle {
-                            sprite: Sprite {
-                                custom_size: Some(size),
-                                color: CELL_COLOR,
-                                ..Default::default()
-                            },
-                            transform: Transform::from_translation(position),
-                            ..Default::default()
-                        })
-                        .insert(BasicCell::new(NewCell {
-                            row,
-                            column,
-                            position,
-                            size,
-                        }));
-                    Cell {
-                        column,
-                        flagged: false,
-                        mine: false,
-                        opened: false,
-                        row,
-                        value: 0,
-                    }
-                })
-                .collect()
+fn config_board(config: &GameConfig) -> Board {
+    let game = match config.seed {
+        Some(seed) => Game::with_seed(config.rows, config.columns, config.mines, seed),
+        None => Game::new(config.rows, config.columns, config.mines),
+    }
+    .with_generation(config.generation);
+    info!("generating board with seed {}", game.seed);
+    Board {
+        clicks: Clicks::default(),
+        counted: false,
+        elapsed: Duration::ZERO,
+        game,
+        history: History::default(),
+        imported: false,
+        question_marks: config.question_marks,
+        ranked: config.ranked,
+    }
+}
+
+fn spawn_board(commands: &mut Commands, board: Board) {
+    let height = board.game.height;
+    let width = board.game.width;
+    let spacing = CELL_SPACING;
+    let size = Vec2::new(CELL_SIZE, CELL_SIZE);
+    let extent = board_size(height, width);
+    let offset = Vec3::new(-(extent.x - size.x) / 2.0, -(extent.y - size.y) / 2.0, 0.0);
+
+    // Add walls
+    let wall_thickness = 10.0;
+    let bounds = wall_bounds(height, width);
+
+    // left
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(-bounds.x / 2.0, 0.0, 0.0),
+            sprite: Sprite {
+                color: Color::PINK,
+                custom_size: Some(Vec2::new(wall_thickness, bounds.y + wall_thickness)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Wall);
+    // right
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(bounds.x / 2.0, 0.0, 0.0),
+            sprite: Sprite {
+                color: Color::PINK,
+                custom_size: Some(Vec2::new(wall_thickness, bounds.y + wall_thickness)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Wall);
+
+    // bottom
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(0.0, -bounds.y / 2.0, 0.0),
+            sprite: Sprite {
+                color: Color::PINK,
+                custom_size: Some(Vec2::new(bounds.x + wall_thickness, wall_thickness)),
+                ..Default::default()
+            },
+            ..Default::default()
         })
-        .collect();
-
-    commands.spawn().insert(Board {
-        cells_unopened: cells.len(),
-        cells,
-        game_over: false,
-        height,
-        initialized: false,
-        width,
-    });
+        .insert(Wall);
+    // top
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(0.0, bounds.y / 2.0, 0.0),
+            sprite: Sprite {
+                color: Color::PINK,
+                custom_size: Some(Vec2::new(bounds.x + wall_thickness, wall_thickness)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Wall);
+
+    for row in 0..height {
+        for column in 0..width {
+            let position = Vec3::new(
+                column as f32 * (size.x + spacing),
+                row as f32 * (size.y + spacing),
+                0.0,
+            ) + offset;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(size),
+                        color: CELL_COLOR,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(position),
+                    ..Default::default()
+                })
+                .insert(BasicCell::new(NewCell {
+                    row,
+                    column,
+                    position,
+                    size,
+                }));
+        }
+    }
+
+    commands.spawn().insert(board);
 }
 
 pub struct BoardPlugin;
@@ -331,16 +692,34 @@ impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_update(AppState::InGame)
-                .with_system(clear_open_cells.after("left_click"))
-                .with_system(chord_solved_cell.after("left_click"))
-                .with_system(game_over.after("left_click"))
-                .with_system(flag_solved_cell.after("right_click")),
+                .with_system(tick_timer.before("game_rules"))
+                .with_system(
+                    apply_actions
+                        .label("game_rules")
+                        .after("left_click")
+                        .after("right_click")
+                        .after("keyboard")
+                        .after("touch"),
+                )
+                .with_system(restore_cells.before("apply_game_changes"))
+                .with_system(
+                    apply_game_changes
+                        .label("apply_game_changes")
+                        .after("game_rules"),
+                )
+                .with_system(autosave.after("game_rules"))
+                .with_system(game_over.label("game_over").after("apply_game_changes"))
+                .with_system(record_outcome.after("apply_game_changes"))
+                .with_system(new_game.after("game_over")),
         );
-        app.add_event::<ClearOpenCellsEvent>();
-        app.add_event::<ChordSolvedCellEvent>();
-        app.add_event::<FlagSolvedCellEvent>();
+        app.add_event::<ActionEvent>();
+        app.add_event::<ActionAppliedEvent>();
+        app.add_event::<GameChangedEvent>();
         app.add_event::<MineClickedEvent>();
         app.add_event::<AllCellsOpenedEvent>();
+        app.add_event::<NewGameEvent>();
+        app.add_event::<LoadGameEvent>();
+        app.add_event::<PlayReplayEvent>();
         app.add_startup_system(generate_board);
     }
 }
@@ -1,4 +1,4 @@
-use super::board::Board;
+use super::board::{Board, CELL_SIZE};
 use crate::AppState;
 
 use bevy::prelude::*;
@@ -7,27 +7,6 @@ pub const CELL_COLOR: bevy::prelude::Color = Color::MIDNIGHT_BLUE;
 
 pub struct ApplyMaterialEvent(pub (usize, usize));
 
-pub const SURROUND: [(isize, isize); 8] = [
-    (-1, -1),
-    (0, -1),
-    (1, -1),
-    (-1, 0),
-    (1, 0),
-    (-1, 1),
-    (0, 1),
-    (1, 1),
-];
-
-#[derive(Debug)]
-pub struct Cell {
-    pub column: usize,
-    pub flagged: bool,
-    pub mine: bool,
-    pub opened: bool,
-    pub row: usize,
-    pub value: u8,
-}
-
 pub struct NewCell {
     pub column: usize,
     pub position: Vec3,
@@ -91,12 +70,15 @@ fn apply_cell_material(
             return;
         };
 
-        let cell = &board.cells[row][col];
-        if cell.value == 0 {
-            sprite.color = Color::GRAY;
+        let cell = &board.game.cells[row][col];
+        sprite.color = if cell.mine {
+            // the only mine that is ever opened is the one that went off
+            Color::RED
+        } else if cell.value == 0 {
+            Color::GRAY
         } else {
-            sprite.color = Color::WHITE;
-        }
+            Color::WHITE
+        };
 
         let texture = if cell.mine {
             asset_server.load("mine.png")
@@ -116,7 +98,7 @@ fn apply_cell_material(
         let child = commands
             .spawn_bundle(SpriteBundle {
                 sprite: Sprite {
-                    custom_size: Some(Vec2::new(22.0, 22.0)),
+                    custom_size: Some(Vec2::new(CELL_SIZE, CELL_SIZE)),
                     ..Default::default()
                 },
 
@@ -129,22 +111,13 @@ fn apply_cell_material(
     }
 }
 
-impl Cell {
-    pub fn surrounds(&self, cell_position: (usize, usize)) -> bool {
-        SURROUND.iter().any(|(row, col)| {
-            self.row as isize + row == cell_position.0 as isize
-                && self.column as isize + col == cell_position.1 as isize
-        })
-    }
-}
-
 pub struct CellPlugin;
 
 impl Plugin for CellPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_update(AppState::InGame)
-                .with_system(apply_cell_material.after("left_click")),
+                .with_system(apply_cell_material.after("apply_game_changes")),
         );
         app.add_event::<ApplyMaterialEvent>();
     }
@@ -0,0 +1,112 @@
+use crate::core::Generation;
+
+use std::error::Error;
+
+/// the smallest board that is allowed across or down
+pub const MIN_SIZE: usize = 5;
+pub const MAX_ROWS: usize = 30;
+pub const MAX_COLUMNS: usize = 50;
+
+/// Board dimensions and mine count used whenever a new board is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    pub columns: usize,
+    pub generation: Generation,
+    pub mines: u16,
+    /// right clicking a flag turns it into a question mark rather than clearing it
+    pub question_marks: bool,
+    /// moves can't be undone, so every win counts for the leaderboard as it was played
+    pub ranked: bool,
+    pub rows: usize,
+    /// the same seed and first click always produce the same board. a random seed is picked when
+    /// this is `None`
+    pub seed: Option<u64>,
+}
+
+impl GameConfig {
+    pub const BEGINNER: GameConfig = GameConfig {
+        columns: 9,
+        generation: Generation::Random,
+        mines: 10,
+        question_marks: true,
+        ranked: false,
+        rows: 9,
+        seed: None,
+    };
+    pub const INTERMEDIATE: GameConfig = GameConfig {
+        columns: 16,
+        generation: Generation::Random,
+        mines: 40,
+        question_marks: true,
+        ranked: false,
+        rows: 16,
+        seed: None,
+    };
+    pub const EXPERT: GameConfig = GameConfig {
+        columns: 30,
+        generation: Generation::Random,
+        mines: 99,
+        question_marks: true,
+        ranked: false,
+        rows: 16,
+        seed: None,
+    };
+
+    /// a board of any size the main menu allows, with at least one mine and room for the first
+    /// click and its neighbours to be free of them
+    pub fn new(rows: usize, columns: usize, mines: u16) -> Result<Self, Box<dyn Error>> {
+        if !(MIN_SIZE..=MAX_ROWS).contains(&rows) {
+            return Err(format!("rows have to be between {} and {}", MIN_SIZE, MAX_ROWS).into());
+        }
+        if !(MIN_SIZE..=MAX_COLUMNS).contains(&columns) {
+            return Err(format!(
+                "columns have to be between {} and {}",
+                MIN_SIZE, MAX_COLUMNS
+            )
+            .into());
+        }
+        let config = GameConfig {
+            columns,
+            generation: Generation::Random,
+            mines,
+            question_marks: true,
+            ranked: false,
+            rows,
+            seed: None,
+        };
+        if mines == 0 || mines as usize > config.max_mines() {
+            return Err(format!("mines have to be between 1 and {}", config.max_mines()).into());
+        }
+        Ok(config)
+    }
+
+    /// the first click and its neighbours always need to be free of mines
+    pub fn max_mines(&self) -> usize {
+        self.rows * self.columns - 9
+    }
+
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        GameConfig { seed, ..self }
+    }
+
+    pub fn with_generation(self, generation: Generation) -> Self {
+        GameConfig { generation, ..self }
+    }
+
+    pub fn with_question_marks(self, question_marks: bool) -> Self {
+        GameConfig {
+            question_marks,
+            ..self
+        }
+    }
+
+    pub fn with_ranked(self, ranked: bool) -> Self {
+        GameConfig { ranked, ..self }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::INTERMEDIATE
+    }
+}
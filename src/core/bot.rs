@@ -0,0 +1,184 @@
+use super::game::{Game, GameState};
+use super::solver::{self, Deduction};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::error::Error;
+
+/// How the bot picks a cell once nothing more can be worked out from the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// any cell that isn't known to be a mine
+    Random,
+    /// the cell least likely to be a mine, falling back to a random one when the chances can't be
+    /// worked out
+    Safest,
+}
+
+/// How a game played by the bot went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// every cell the bot opened, including the first one. flags are never placed
+    pub clicks: u32,
+    /// the cells opened without being sure they were safe. the first click is always safe so it
+    /// isn't counted
+    pub guesses: u32,
+    pub three_bv: u32,
+    pub won: bool,
+}
+
+/// plays `game` to the end from the middle of the board, opening every cell the solver proves
+/// safe and guessing with `strategy` when it gets stuck. the same game and strategy always play
+/// out the same way. fails if the board has too many mines to be dealt
+pub fn play(game: &mut Game, strategy: Strategy) -> Result<Outcome, Box<dyn Error>> {
+    let mut rng = StdRng::seed_from_u64(game.seed);
+    let mut outcome = Outcome {
+        clicks: 1,
+        guesses: 0,
+        three_bv: 0,
+        won: false,
+    };
+    game.reveal((game.height / 2, game.width / 2))?;
+    outcome.three_bv = game.three_bv();
+
+    while !game.is_over() {
+        let deductions = solver::deduce(game);
+        let safe: Vec<(usize, usize)> = deductions
+            .iter()
+            .filter_map(|deduction| match deduction {
+                Deduction::Safe(position) => Some(*position),
+                Deduction::Mine(_) => None,
+            })
+            .collect();
+
+        if safe.is_empty() {
+            let mines: Vec<(usize, usize)> = deductions
+                .iter()
+                .filter_map(|deduction| match deduction {
+                    Deduction::Mine(position) => Some(*position),
+                    Deduction::Safe(_) => None,
+                })
+                .collect();
+            let guess = guess(game, strategy, &mines, &mut rng);
+            outcome.clicks += 1;
+            outcome.guesses += 1;
+            game.reveal(guess)?;
+            continue;
+        }
+
+        for (row, col) in safe {
+            // an earlier cell may have flooded over this one
+            if !game.cells[row][col].opened {
+                outcome.clicks += 1;
+                game.reveal((row, col))?;
+            }
+        }
+    }
+
+    outcome.won = game.state == GameState::Won;
+    Ok(outcome)
+}
+
+fn guess(
+    game: &Game,
+    strategy: Strategy,
+    mines: &[(usize, usize)],
+    rng: &mut StdRng,
+) -> (usize, usize) {
+    if strategy == Strategy::Safest {
+        if let Some(chances) = solver::probabilities(game) {
+            let safest = chances
+                .iter()
+                .enumerate()
+                .flat_map(|(row, cells)| {
+                    cells
+                        .iter()
+                        .enumerate()
+                        .filter_map(move |(col, chance)| chance.map(|chance| ((row, col), chance)))
+                })
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+            if let Some((position, _)) = safest {
+                return position;
+            }
+        }
+    }
+
+    let candidates: Vec<(usize, usize)> = game
+        .cells
+        .iter()
+        .flatten()
+        .filter(|cell| !cell.opened && !mines.contains(&(cell.row, cell.column)))
+        .map(|cell| (cell.row, cell.column))
+        .collect();
+    *candidates
+        .choose(rng)
+        .expect("a game still being played has an unopened safe cell")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Generation;
+
+    #[test]
+    fn a_seed_plays_out_the_same_way() {
+        for strategy in [Strategy::Random, Strategy::Safest] {
+            for seed in 0..5 {
+                let mut first = Game::with_seed(16, 16, 40, seed);
+                let mut second = Game::with_seed(16, 16, 40, seed);
+                let outcome = play(&mut first, strategy).unwrap();
+                assert_eq!(play(&mut second, strategy).unwrap(), outcome);
+                let opened = |game: &Game| -> Vec<bool> {
+                    game.cells
+                        .iter()
+                        .flatten()
+                        .map(|cell| cell.opened)
+                        .collect()
+                };
+                assert_eq!(opened(&first), opened(&second));
+            }
+        }
+    }
+
+    #[test]
+    fn solvable_boards_are_won_without_guessing() {
+        // the 2 between the mines can only be safe
+        let mut game = Game::with_mines(3, 5, &[(0, 4), (2, 4)]);
+        let outcome = play(&mut game, Strategy::Safest).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome {
+                clicks: 2,
+                guesses: 0,
+                three_bv: 2,
+                won: true,
+            }
+        );
+
+        let mut solvable = 0;
+        for seed in 0..5 {
+            let start = (4, 4);
+            let mut game = Game::with_seed(9, 9, 10, seed).with_generation(Generation::no_guess());
+            let mut dealt = game.clone();
+            dealt.fill_board(start).unwrap();
+            if !solver::solvable(&dealt, start) {
+                continue;
+            }
+            let outcome = play(&mut game, Strategy::Safest).unwrap();
+            assert!(outcome.won);
+            assert_eq!(outcome.guesses, 0);
+            solvable += 1;
+        }
+        assert!(solvable > 0);
+    }
+
+    #[test]
+    fn guesses_are_counted() {
+        // the last two cells are a coin flip that nothing on the board can settle
+        let mut game = Game::with_mines(2, 5, &[(0, 4)]);
+        let outcome = play(&mut game, Strategy::Safest).unwrap();
+        assert_eq!(outcome.guesses, 1);
+        assert_eq!(outcome.clicks, 2);
+        assert_eq!(outcome.won, game.cells[1][4].opened);
+        assert_eq!(outcome.won, game.state == GameState::Won);
+    }
+}
@@ -0,0 +1,54 @@
+pub const SURROUND: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// What the player has put on a covered cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Blank,
+    /// a mine is known to be here. flags count towards chording and the mines left
+    Flag,
+    /// a reminder to come back to the cell, which changes nothing about how it is played
+    Question,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub column: usize,
+    pub mark: Mark,
+    pub mine: bool,
+    pub opened: bool,
+    pub row: usize,
+    pub value: u8,
+}
+
+impl Cell {
+    pub fn new(row: usize, column: usize) -> Self {
+        Cell {
+            column,
+            mark: Mark::Blank,
+            mine: false,
+            opened: false,
+            row,
+            value: 0,
+        }
+    }
+
+    pub fn flagged(&self) -> bool {
+        self.mark == Mark::Flag
+    }
+
+    pub fn surrounds(&self, cell_position: (usize, usize)) -> bool {
+        SURROUND.iter().any(|(row, col)| {
+            self.row as isize + row == cell_position.0 as isize
+                && self.column as isize + col == cell_position.1 as isize
+        })
+    }
+}
@@ -0,0 +1,640 @@
+use super::cell::{Cell, Mark, SURROUND};
+use super::solver;
+
+use rand::{
+    rngs::{SmallRng, StdRng},
+    Rng, SeedableRng,
+};
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// How the mines are laid out once the first cell is revealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generation {
+    /// anywhere outside of the first click and its neighbours
+    Random,
+    /// keeps laying out boards until one can be cleared from the first click without guessing.
+    /// the budget is a number of attempts rather than a duration so that a seed gives the same
+    /// board on every machine. the last attempt is kept if none of them were solvable
+    NoGuess { max_attempts: u32 },
+}
+
+impl Generation {
+    /// enough attempts to almost always find a solvable expert board. it caps the number of boards
+    /// laid out and checked, not how long that takes, so bigger boards take longer
+    pub fn no_guess() -> Self {
+        Generation::NoGuess { max_attempts: 200 }
+    }
+}
+
+/// A single thing that happened to the board as the result of a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Opened((usize, usize)),
+    Flagged((usize, usize)),
+    Unflagged((usize, usize)),
+    Questioned((usize, usize)),
+    Unquestioned((usize, usize)),
+    /// an opened cell was covered again by undoing the move that opened it
+    Closed((usize, usize)),
+    /// a mine was opened at the given position
+    Lost((usize, usize)),
+    Won,
+    /// a lost or won game was taken back to being played
+    Resumed,
+}
+
+impl Change {
+    /// the change that takes this one back. the game's state before `Resumed` isn't known so it
+    /// is its own inverse
+    pub fn inverse(self) -> Change {
+        match self {
+            Change::Opened(position) => Change::Closed(position),
+            Change::Closed(position) => Change::Opened(position),
+            Change::Flagged(position) => Change::Unflagged(position),
+            Change::Unflagged(position) => Change::Flagged(position),
+            Change::Questioned(position) => Change::Unquestioned(position),
+            Change::Unquestioned(position) => Change::Questioned(position),
+            Change::Lost(_) | Change::Won | Change::Resumed => Change::Resumed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub cells: Vec<Vec<Cell>>,
+    pub cells_unopened: usize,
+    pub generation: Generation,
+    pub height: usize,
+    pub initialized: bool,
+    pub mines: u16,
+    pub seed: u64,
+    pub state: GameState,
+    pub width: usize,
+}
+
+impl Game {
+    pub fn new(height: usize, width: usize, mines: u16) -> Self {
+        Game::with_seed(height, width, mines, SmallRng::from_entropy().gen())
+    }
+
+    pub fn with_seed(height: usize, width: usize, mines: u16, seed: u64) -> Self {
+        let cells = (0..height)
+            .map(|row| (0..width).map(|column| Cell::new(row, column)).collect())
+            .collect();
+
+        Game {
+            cells,
+            cells_unopened: (height * width).saturating_sub(mines as usize),
+            generation: Generation::Random,
+            height,
+            initialized: false,
+            mines,
+            seed,
+            state: GameState::Playing,
+            width,
+        }
+    }
+
+    /// a game with the mines already laid out at the given positions, ready to be played from
+    /// any cell. the positions must not repeat
+    pub fn with_mines(height: usize, width: usize, mines: &[(usize, usize)]) -> Self {
+        let mut game = Game::with_seed(height, width, mines.len() as u16, 0);
+        for (row, col) in mines {
+            game.cells[*row][*col].mine = true;
+        }
+        game.number_cells();
+        game.initialized = true;
+        game
+    }
+
+    pub fn with_generation(self, generation: Generation) -> Self {
+        Game { generation, ..self }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state != GameState::Playing
+    }
+
+    /// every in bounds position touching `position`
+    pub fn neighbours(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let (height, width) = (self.height as isize, self.width as isize);
+        SURROUND.into_iter().filter_map(move |(row, col)| {
+            let r = position.0 as isize + row;
+            let c = position.1 as isize + col;
+            if (0..height).contains(&r) && (0..width).contains(&c) {
+                return Some((r as usize, c as usize));
+            }
+            None
+        })
+    }
+
+    /// places the mines anywhere except `start` and the cells touching it, then numbers every cell.
+    /// the layout only depends on the seed, the generation and `start`
+    pub fn fill_board(&mut self, start: (usize, usize)) -> Result<(), Box<dyn Error>> {
+        let board_size = self.height * self.width;
+        let safe_cells = 1 + self.neighbours(start).count();
+
+        if self.mines as usize > board_size - safe_cells {
+            return Err("You have requested too many mines for this size of board".into());
+        }
+
+        self.cells_unopened = board_size - self.mines as usize;
+        self.initialized = true;
+
+        // StdRng keeps the layout for a seed the same on wasm32 and native
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        match self.generation {
+            Generation::Random => self.place_mines(start, &mut rng),
+            Generation::NoGuess { max_attempts } => {
+                for _ in 0..max_attempts.max(1) {
+                    self.place_mines(start, &mut rng);
+                    if solver::solvable(self, start) {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn place_mines(&mut self, start: (usize, usize), rng: &mut StdRng) {
+        let rows = self.height;
+        let columns = self.width;
+        let mut curr_mines = 0;
+
+        self.cells
+            .iter_mut()
+            .flatten()
+            .for_each(|cell| cell.mine = false);
+
+        while curr_mines < self.mines {
+            // u32 ranges draw the same numbers on 32 and 64 bit targets
+            let row = rng.gen_range(0..rows as u32) as usize;
+            let col = rng.gen_range(0..columns as u32) as usize;
+            let cell = &mut self.cells[row][col];
+            if row == start.0 && col == start.1 || cell.surrounds((start.0, start.1)) {
+                continue;
+            }
+
+            if !cell.mine {
+                cell.mine = true;
+                curr_mines += 1;
+            }
+        }
+
+        self.number_cells();
+    }
+
+    /// counts the mines around every cell
+    pub fn number_cells(&mut self) {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let value = self
+                    .neighbours((row, col))
+                    .filter(|(r, c)| self.cells[*r][*c].mine)
+                    .count();
+                self.cells[row][col].value = value as u8;
+            }
+        }
+    }
+
+    /// the fewest clicks that clear the board without placing any flags, known as 3BV: one for
+    /// every opening of cells without surrounding mines and one for every numbered cell that no
+    /// opening reaches
+    pub fn three_bv(&self) -> u32 {
+        self.count_three_bv(false)
+    }
+
+    /// the part of the 3BV that has been cleared: every opening whose cells are all opened and
+    /// every opened numbered cell outside of them
+    pub fn solved_three_bv(&self) -> u32 {
+        self.count_three_bv(true)
+    }
+
+    fn count_three_bv(&self, solved_only: bool) -> u32 {
+        let mut reached = vec![vec![false; self.width]; self.height];
+        let mut three_bv = 0;
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let cell = &self.cells[row][col];
+                if reached[row][col] || cell.mine || cell.value != 0 {
+                    continue;
+                }
+                reached[row][col] = true;
+                let mut solved = cell.opened;
+                let mut queue = vec![(row, col)];
+                while let Some(curr) = queue.pop() {
+                    for (r, c) in self.neighbours(curr) {
+                        if reached[r][c] {
+                            continue;
+                        }
+                        reached[r][c] = true;
+                        solved &= self.cells[r][c].opened;
+                        if self.cells[r][c].value == 0 {
+                            queue.push((r, c));
+                        }
+                    }
+                }
+                if solved || !solved_only {
+                    three_bv += 1;
+                }
+            }
+        }
+        let numbered = self
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.mine && !reached[cell.row][cell.column])
+            .filter(|cell| cell.opened || !solved_only)
+            .count();
+        three_bv + numbered as u32
+    }
+
+    /// opens the cell at `position`, flooding outwards if it has no surrounding mines. the mines
+    /// are placed on the first reveal so that it is always safe, which fails if there are too
+    /// many of them to leave room around the cell
+    pub fn reveal(&mut self, (row, col): (usize, usize)) -> Result<Vec<Change>, Box<dyn Error>> {
+        let mut changes = vec![];
+        if self.is_over() {
+            return Ok(changes);
+        }
+
+        if !self.initialized {
+            self.fill_board((row, col))?;
+        }
+
+        let cell = &mut self.cells[row][col];
+        if cell.opened || cell.flagged() {
+            return Ok(changes);
+        }
+
+        // question marks come off the cells they were on once those are opened
+        if cell.mark == Mark::Question {
+            cell.mark = Mark::Blank;
+            changes.push(Change::Unquestioned((row, col)));
+        }
+        cell.opened = true;
+        changes.push(Change::Opened((row, col)));
+
+        if cell.mine {
+            self.state = GameState::Lost;
+            changes.push(Change::Lost((row, col)));
+            return Ok(changes);
+        }
+
+        self.cells_unopened -= 1;
+        if cell.value == 0 {
+            self.open_surrounding((row, col), &mut changes);
+        }
+        self.check_won(&mut changes);
+        Ok(changes)
+    }
+
+    /// opens everything around an opened cell once all of its mines have been flagged
+    pub fn chord(&mut self, (row, col): (usize, usize)) -> Vec<Change> {
+        let mut changes = vec![];
+        let chord_cell = &self.cells[row][col];
+        if self.is_over() || !chord_cell.opened {
+            return changes;
+        }
+
+        let flagged_mines = self
+            .neighbours((row, col))
+            .filter(|(r, c)| {
+                let cell = &self.cells[*r][*c];
+                !cell.opened && cell.mine && cell.flagged()
+            })
+            .count();
+
+        if flagged_mines == chord_cell.value as usize {
+            self.open_surrounding((row, col), &mut changes);
+            self.check_won(&mut changes);
+        }
+        changes
+    }
+
+    /// moves an unopened cell on to its next mark, from blank to a flag and back again, with a
+    /// question mark in between when `question_marks` is set
+    pub fn cycle_mark(&mut self, (row, col): (usize, usize), question_marks: bool) -> Vec<Change> {
+        let cell = &mut self.cells[row][col];
+        if self.state != GameState::Playing || cell.opened {
+            return vec![];
+        }
+
+        let position = (row, col);
+        match cell.mark {
+            Mark::Blank => {
+                cell.mark = Mark::Flag;
+                vec![Change::Flagged(position)]
+            }
+            Mark::Flag if question_marks => {
+                cell.mark = Mark::Question;
+                vec![Change::Unflagged(position), Change::Questioned(position)]
+            }
+            Mark::Flag => {
+                cell.mark = Mark::Blank;
+                vec![Change::Unflagged(position)]
+            }
+            Mark::Question => {
+                cell.mark = Mark::Blank;
+                vec![Change::Unquestioned(position)]
+            }
+        }
+    }
+
+    /// flags every unopened cell around an opened cell when there are exactly as many of them as
+    /// its value
+    pub fn auto_flag(&mut self, (row, col): (usize, usize)) -> Vec<Change> {
+        let flag_cell = &self.cells[row][col];
+        if self.is_over() || !flag_cell.opened {
+            return vec![];
+        }
+
+        let unopened_cells: Vec<(usize, usize)> = self
+            .neighbours((row, col))
+            .filter(|(r, c)| !self.cells[*r][*c].opened)
+            .collect();
+
+        if unopened_cells.len() != flag_cell.value as usize {
+            return vec![];
+        }
+
+        let mut changes = vec![];
+        for (r, c) in unopened_cells {
+            let cell = &mut self.cells[r][c];
+            match cell.mark {
+                Mark::Flag => continue,
+                Mark::Question => changes.push(Change::Unquestioned((r, c))),
+                Mark::Blank => {}
+            }
+            cell.mark = Mark::Flag;
+            changes.push(Change::Flagged((r, c)));
+        }
+        changes
+    }
+
+    /// makes a change that was recorded earlier without applying the rules again
+    pub fn apply(&mut self, change: Change) {
+        match change {
+            Change::Opened((row, col)) => {
+                let cell = &mut self.cells[row][col];
+                if !cell.opened {
+                    cell.opened = true;
+                    if !cell.mine {
+                        self.cells_unopened -= 1;
+                    }
+                }
+            }
+            Change::Closed((row, col)) => {
+                let cell = &mut self.cells[row][col];
+                if cell.opened {
+                    cell.opened = false;
+                    if !cell.mine {
+                        self.cells_unopened += 1;
+                    }
+                }
+            }
+            Change::Flagged((row, col)) => self.cells[row][col].mark = Mark::Flag,
+            Change::Questioned((row, col)) => self.cells[row][col].mark = Mark::Question,
+            Change::Unflagged((row, col)) | Change::Unquestioned((row, col)) => {
+                self.cells[row][col].mark = Mark::Blank
+            }
+            Change::Lost(_) => self.state = GameState::Lost,
+            Change::Won => self.state = GameState::Won,
+            Change::Resumed => self.state = GameState::Playing,
+        }
+    }
+
+    fn open_surrounding(&mut self, start: (usize, usize), changes: &mut Vec<Change>) {
+        let mut queue = vec![start];
+        while let Some(curr) = queue.pop() {
+            for (row, col) in self.neighbours(curr) {
+                let cell = &mut self.cells[row][col];
+                if cell.opened || cell.mine || cell.flagged() {
+                    continue;
+                }
+                if cell.value == 0 {
+                    queue.push((row, col));
+                }
+
+                if cell.mark == Mark::Question {
+                    cell.mark = Mark::Blank;
+                    changes.push(Change::Unquestioned((row, col)));
+                }
+                cell.opened = true;
+                self.cells_unopened -= 1;
+                changes.push(Change::Opened((row, col)));
+            }
+        }
+    }
+
+    /// a won game flags every mine that was left unflagged
+    fn check_won(&mut self, changes: &mut Vec<Change>) {
+        if self.cells_unopened == 0 && self.state == GameState::Playing {
+            for cell in self.cells.iter_mut().flatten() {
+                if cell.mine && !cell.flagged() {
+                    let position = (cell.row, cell.column);
+                    if cell.mark == Mark::Question {
+                        changes.push(Change::Unquestioned(position));
+                    }
+                    cell.mark = Mark::Flag;
+                    changes.push(Change::Flagged(position));
+                }
+            }
+            self.state = GameState::Won;
+            changes.push(Change::Won);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opened(changes: &[Change]) -> usize {
+        changes
+            .iter()
+            .filter(|change| matches!(change, Change::Opened(_)))
+            .count()
+    }
+
+    #[test]
+    fn first_reveal_is_safe() {
+        for seed in 0..50 {
+            let mut game = Game::with_seed(9, 9, 10, seed);
+            game.reveal((4, 4)).unwrap();
+            assert_eq!(game.state, GameState::Playing);
+            assert!(!game.cells[4][4].mine);
+            assert!(game.neighbours((4, 4)).all(|(r, c)| !game.cells[r][c].mine));
+            assert_eq!(
+                game.cells.iter().flatten().filter(|cell| cell.mine).count(),
+                10
+            );
+        }
+    }
+
+    fn mines(game: &Game) -> Vec<(usize, usize)> {
+        game.cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.mine)
+            .map(|cell| (cell.row, cell.column))
+            .collect()
+    }
+
+    #[test]
+    fn a_seed_always_deals_the_same_board() {
+        for generation in [Generation::Random, Generation::no_guess()] {
+            let mut first = Game::with_seed(16, 30, 99, 7).with_generation(generation);
+            let mut second = Game::with_seed(16, 30, 99, 7).with_generation(generation);
+            first.reveal((8, 15)).unwrap();
+            second.reveal((8, 15)).unwrap();
+            assert_eq!(mines(&first), mines(&second));
+
+            // starting somewhere else keeps that start safe under the same seed
+            let mut elsewhere = Game::with_seed(16, 30, 99, 7).with_generation(generation);
+            elsewhere.reveal((0, 0)).unwrap();
+            assert_eq!(elsewhere.state, GameState::Playing);
+            assert!(!elsewhere.cells[0][0].mine);
+            assert!(elsewhere
+                .neighbours((0, 0))
+                .all(|(r, c)| !elsewhere.cells[r][c].mine));
+        }
+    }
+
+    #[test]
+    fn too_many_mines_fail_to_deal() {
+        let mut game = Game::with_seed(5, 5, 17, 0);
+        assert!(game.reveal((2, 2)).is_err());
+        assert!(!game.initialized);
+    }
+
+    #[test]
+    fn reveal_floods_and_wins() {
+        let mut game = Game::with_mines(3, 3, &[(2, 2)]);
+        let changes = game.reveal((0, 0)).unwrap();
+        assert_eq!(opened(&changes), 8);
+        assert_eq!(game.state, GameState::Won);
+        // the mine left over is flagged for the win
+        assert!(changes.contains(&Change::Flagged((2, 2))));
+        assert_eq!(changes.last(), Some(&Change::Won));
+    }
+
+    #[test]
+    fn flood_stops_at_numbers() {
+        let mut game = Game::with_mines(3, 5, &[(0, 4), (2, 4)]);
+        let changes = game.reveal((1, 0)).unwrap();
+        // the first three columns are empty or touch nothing but the fourth, which is numbered
+        assert_eq!(opened(&changes), 12);
+        assert!(!game.cells[1][4].opened);
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn revealing_a_mine_loses() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        let changes = game.reveal((0, 0)).unwrap();
+        assert_eq!(changes, vec![Change::Opened((0, 0)), Change::Lost((0, 0))]);
+        assert_eq!(game.state, GameState::Lost);
+        assert!(game.reveal((2, 2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn flags_cycle_and_block_reveals() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        assert_eq!(
+            game.cycle_mark((0, 0), false),
+            vec![Change::Flagged((0, 0))]
+        );
+        assert!(game.reveal((0, 0)).unwrap().is_empty());
+        assert_eq!(
+            game.cycle_mark((0, 0), false),
+            vec![Change::Unflagged((0, 0))]
+        );
+
+        game.cycle_mark((0, 0), true);
+        assert_eq!(
+            game.cycle_mark((0, 0), true),
+            vec![Change::Unflagged((0, 0)), Change::Questioned((0, 0))]
+        );
+        assert_eq!(game.cells[0][0].mark, Mark::Question);
+        assert_eq!(
+            game.cycle_mark((0, 0), true),
+            vec![Change::Unquestioned((0, 0))]
+        );
+    }
+
+    #[test]
+    fn chord_needs_the_flags() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        game.reveal((1, 1)).unwrap();
+        assert_eq!(game.cells[1][1].value, 1);
+        assert!(game.chord((1, 1)).is_empty());
+
+        game.cycle_mark((0, 0), false);
+        let changes = game.chord((1, 1));
+        assert_eq!(opened(&changes), 7);
+        assert_eq!(game.state, GameState::Won);
+    }
+
+    #[test]
+    fn auto_flag_flags_the_only_cells_left() {
+        let mut game = Game::with_mines(2, 2, &[(0, 0)]);
+        game.reveal((1, 1)).unwrap();
+        game.reveal((0, 1)).unwrap();
+        assert_eq!(game.state, GameState::Playing);
+        assert!(game.auto_flag((1, 1)).is_empty());
+
+        let mut game = Game::with_mines(2, 3, &[(0, 0)]);
+        game.reveal((0, 1)).unwrap();
+        game.reveal((1, 0)).unwrap();
+        game.reveal((1, 1)).unwrap();
+        assert_eq!(game.auto_flag((1, 0)), vec![Change::Flagged((0, 0))]);
+    }
+
+    #[test]
+    fn three_bv_counts_openings_and_numbers_outside_of_them() {
+        // one opening over the first four columns and the 2 between the mines on its own
+        let game = Game::with_mines(3, 5, &[(0, 4), (2, 4)]);
+        assert_eq!(game.three_bv(), 2);
+
+        // no openings at all, so every number is a click of its own
+        let game = Game::with_mines(3, 3, &[(1, 1)]);
+        assert_eq!(game.three_bv(), 8);
+    }
+
+    #[test]
+    fn numbers_between_openings_are_counted_once() {
+        // the 2 in the middle touches both openings
+        let game = Game::with_mines(3, 3, &[(0, 0), (2, 2)]);
+        assert_eq!(game.cells[1][1].value, 2);
+        assert_eq!(game.three_bv(), 2);
+    }
+
+    #[test]
+    fn solved_three_bv_counts_what_was_cleared() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0), (2, 2)]);
+        assert_eq!(game.solved_three_bv(), 0);
+        game.reveal((2, 0)).unwrap();
+        // the shared 2 is open but the other opening isn't cleared yet
+        assert!(game.cells[1][1].opened);
+        assert_eq!(game.solved_three_bv(), 1);
+        game.reveal((0, 2)).unwrap();
+        assert_eq!(game.state, GameState::Won);
+        assert_eq!(game.solved_three_bv(), 2);
+
+        let mut game = Game::with_mines(3, 3, &[(1, 1)]);
+        game.reveal((0, 0)).unwrap();
+        game.reveal((2, 2)).unwrap();
+        assert_eq!(game.solved_three_bv(), 2);
+        assert_eq!(game.three_bv(), 8);
+    }
+}
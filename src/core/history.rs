@@ -0,0 +1,183 @@
+use super::game::{Change, Game};
+
+/// The moves made so far, each kept as the changes it made so it can be taken back and made again.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<Vec<Change>>,
+    undone: Vec<Vec<Change>>,
+    /// how many moves have been taken back over the whole game, including ones made again since
+    pub undos: u32,
+}
+
+impl History {
+    /// adds a move that was just made. moves that didn't change anything aren't kept, and a new
+    /// move forgets the ones that were undone
+    pub fn record(&mut self, changes: &[Change]) {
+        if changes.is_empty() {
+            return;
+        }
+        self.done.push(changes.to_vec());
+        self.undone.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// takes back the last move, returning the changes that did so
+    pub fn undo(&mut self, game: &mut Game) -> Vec<Change> {
+        let changes = if let Some(changes) = self.done.pop() {
+            changes
+        } else {
+            return vec![];
+        };
+
+        let inverse: Vec<Change> = changes
+            .iter()
+            .rev()
+            .map(|change| change.inverse())
+            .collect();
+        for change in inverse.iter() {
+            game.apply(*change);
+        }
+        self.undone.push(changes);
+        self.undos += 1;
+        inverse
+    }
+
+    /// makes the last undone move again, returning its changes
+    pub fn redo(&mut self, game: &mut Game) -> Vec<Change> {
+        let changes = if let Some(changes) = self.undone.pop() {
+            changes
+        } else {
+            return vec![];
+        };
+
+        for change in changes.iter() {
+            game.apply(*change);
+        }
+        self.done.push(changes.clone());
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GameState, Mark};
+
+    /// everything about the board that a move can change
+    fn snapshot(game: &Game) -> (Vec<(bool, Mark)>, GameState, usize) {
+        let cells = game
+            .cells
+            .iter()
+            .flatten()
+            .map(|cell| (cell.opened, cell.mark))
+            .collect();
+        (cells, game.state, game.cells_unopened)
+    }
+
+    #[test]
+    fn undoing_every_move_gives_back_the_starting_board() {
+        let mut game = Game::with_mines(3, 5, &[(0, 4), (2, 4)]);
+        let mut history = History::default();
+        let start = snapshot(&game);
+
+        history.record(&game.reveal((1, 0)).unwrap());
+        history.record(&game.cycle_mark((0, 4), false));
+        history.record(&game.reveal((1, 4)).unwrap());
+        assert_eq!(game.state, GameState::Won);
+
+        for _ in 0..3 {
+            assert!(!history.undo(&mut game).is_empty());
+        }
+        assert_eq!(snapshot(&game), start);
+        assert!(!history.can_undo());
+        assert!(history.undo(&mut game).is_empty());
+    }
+
+    #[test]
+    fn redo_makes_undone_moves_again_until_a_new_move() {
+        let mut game = Game::with_mines(3, 5, &[(0, 4), (2, 4)]);
+        let mut history = History::default();
+        history.record(&game.reveal((1, 0)).unwrap());
+        history.record(&game.cycle_mark((0, 4), false));
+        let played = snapshot(&game);
+
+        history.undo(&mut game);
+        history.undo(&mut game);
+        history.redo(&mut game);
+        history.redo(&mut game);
+        assert_eq!(snapshot(&game), played);
+        assert!(!history.can_redo());
+
+        history.undo(&mut game);
+        assert!(history.can_redo());
+        history.record(&game.cycle_mark((2, 4), false));
+        assert!(!history.can_redo());
+        assert!(history.redo(&mut game).is_empty());
+        assert_eq!(game.cells[0][4].mark, Mark::Blank);
+        assert_eq!(game.cells[2][4].mark, Mark::Flag);
+    }
+
+    #[test]
+    fn undoing_a_mine_goes_back_to_playing() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        let mut history = History::default();
+        history.record(&game.reveal((1, 1)).unwrap());
+        let before = snapshot(&game);
+
+        history.record(&game.reveal((0, 0)).unwrap());
+        assert_eq!(game.state, GameState::Lost);
+        let undone = history.undo(&mut game);
+        assert!(undone.contains(&Change::Resumed));
+        assert!(undone.contains(&Change::Closed((0, 0))));
+        assert_eq!(snapshot(&game), before);
+        assert!(!game.cells[0][0].opened);
+        assert_eq!(
+            game.cycle_mark((0, 0), false),
+            vec![Change::Flagged((0, 0))]
+        );
+    }
+
+    #[test]
+    fn undoing_marks_steps_back_through_them() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        let mut history = History::default();
+        for _ in 0..3 {
+            history.record(&game.cycle_mark((0, 0), true));
+        }
+        assert_eq!(game.cells[0][0].mark, Mark::Blank);
+
+        history.undo(&mut game);
+        assert_eq!(game.cells[0][0].mark, Mark::Question);
+        history.undo(&mut game);
+        assert_eq!(game.cells[0][0].mark, Mark::Flag);
+        history.undo(&mut game);
+        assert_eq!(game.cells[0][0].mark, Mark::Blank);
+    }
+
+    #[test]
+    fn undos_are_counted_even_once_made_again() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0)]);
+        let mut history = History::default();
+        // moves that change nothing aren't kept, so there is nothing to undo
+        history.record(&game.chord((1, 1)));
+        history.undo(&mut game);
+        assert_eq!(history.undos, 0);
+
+        history.record(&game.cycle_mark((0, 0), false));
+        history.record(&game.reveal((1, 1)).unwrap());
+        history.undo(&mut game);
+        history.redo(&mut game);
+        history.undo(&mut game);
+        history.undo(&mut game);
+        assert_eq!(history.undos, 3);
+        history.redo(&mut game);
+        assert_eq!(history.undos, 3);
+    }
+}
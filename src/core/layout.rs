@@ -0,0 +1,259 @@
+//! Mine layouts written out as text grids or in the mbf format used by other minesweeper clones,
+//! so boards can be reproduced exactly or built by hand.
+use super::cell::Mark;
+use super::game::{Game, GameState};
+use crate::config::{MAX_COLUMNS, MAX_ROWS, MIN_SIZE};
+
+use std::error::Error;
+
+/// one line per row from the top of the board down, the way it looks on screen. `.` is a covered
+/// cell and `*` a covered mine, `f` and `F` are the same with a flag on them, `0` to `8` are
+/// opened cells and `X` is a mine that was opened. question marks aren't kept
+pub fn to_text(game: &Game) -> String {
+    let mut out = String::new();
+    for row in game.cells.iter().rev() {
+        for cell in row {
+            out.push(match (cell.opened, cell.mine, cell.flagged()) {
+                (true, true, _) => 'X',
+                (true, false, _) => (b'0' + cell.value) as char,
+                (false, true, true) => 'F',
+                (false, true, false) => '*',
+                (false, false, true) => 'f',
+                (false, false, false) => '.',
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// reads a grid written by `to_text`. blank lines are skipped, and numbers on opened cells have to
+/// match the mines around them
+pub fn from_text(text: &str) -> Result<Game, Box<dyn Error>> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let height = lines.len();
+    let width = lines.first().map(|line| line.len()).unwrap_or(0);
+    if width == 0 {
+        return Err("the layout is empty".into());
+    }
+    if lines.iter().any(|line| line.len() != width) {
+        return Err("every row of the layout has to be the same length".into());
+    }
+    check_size(height, width)?;
+
+    // the first line is the top of the board, which is the last row
+    let grid: Vec<&[u8]> = lines.iter().rev().map(|line| line.as_bytes()).collect();
+    let mut mines = vec![];
+    for (row, line) in grid.iter().enumerate() {
+        for (col, letter) in line.iter().enumerate() {
+            match letter {
+                b'*' | b'F' | b'X' => mines.push((row, col)),
+                b'.' | b'f' | b'0'..=b'8' => {}
+                _ => return Err(format!("unknown cell `{}`", *letter as char).into()),
+            }
+        }
+    }
+    if mines.len() > u16::MAX as usize {
+        return Err("too many mines".into());
+    }
+
+    let mut game = Game::with_mines(height, width, &mines);
+    for (row, line) in grid.iter().enumerate() {
+        for (col, letter) in line.iter().enumerate() {
+            let cell = &mut game.cells[row][col];
+            match letter {
+                b'f' | b'F' => cell.mark = Mark::Flag,
+                b'X' => {
+                    cell.opened = true;
+                    game.state = GameState::Lost;
+                }
+                b'0'..=b'8' => {
+                    if letter - b'0' != cell.value {
+                        return Err(format!(
+                            "the cell in row {} column {} says {} but has {} mines around it",
+                            row, col, *letter as char, cell.value
+                        )
+                        .into());
+                    }
+                    cell.opened = true;
+                    game.cells_unopened -= 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    if game.cells_unopened == 0 && game.state == GameState::Playing {
+        game.state = GameState::Won;
+    }
+    Ok(game)
+}
+
+/// a byte each for the width and height, the number of mines as two big endian bytes, then the
+/// column and row of every mine counting from the top left. only the mines are kept
+pub fn to_mbf(game: &Game) -> Result<Vec<u8>, Box<dyn Error>> {
+    if game.width > u8::MAX as usize || game.height > u8::MAX as usize {
+        return Err("mbf only holds boards up to 255 cells across".into());
+    }
+
+    let mut out = vec![game.width as u8, game.height as u8];
+    let mines: Vec<(usize, usize)> = game
+        .cells
+        .iter()
+        .flatten()
+        .filter(|cell| cell.mine)
+        .map(|cell| (cell.row, cell.column))
+        .collect();
+    out.extend_from_slice(&(mines.len() as u16).to_be_bytes());
+    for (row, col) in mines {
+        out.push(col as u8);
+        out.push((game.height - 1 - row) as u8);
+    }
+    Ok(out)
+}
+
+pub fn from_mbf(bytes: &[u8]) -> Result<Game, Box<dyn Error>> {
+    let (width, height, count, positions) = match bytes {
+        [width, height, high, low, positions @ ..] => (
+            *width as usize,
+            *height as usize,
+            u16::from_be_bytes([*high, *low]) as usize,
+            positions,
+        ),
+        _ => return Err("mbf layouts start with four bytes of size".into()),
+    };
+    if width == 0 || height == 0 {
+        return Err("the layout is empty".into());
+    }
+    check_size(height, width)?;
+    if positions.len() != count * 2 {
+        return Err(format!("expected {} mines but found {}", count, positions.len() / 2).into());
+    }
+
+    let mut mines = vec![];
+    let mut placed = vec![false; width * height];
+    for position in positions.chunks(2) {
+        let (col, y) = (position[0] as usize, position[1] as usize);
+        if col >= width || y >= height {
+            return Err(format!("the mine at {} {} is outside of the board", col, y).into());
+        }
+        if placed[y * width + col] {
+            return Err(format!("there are two mines at {} {}", col, y).into());
+        }
+        placed[y * width + col] = true;
+        mines.push((height - 1 - y, col));
+    }
+    Ok(Game::with_mines(height, width, &mines))
+}
+
+/// layouts are played on the same boards as any other game, so they keep to the same sizes
+fn check_size(height: usize, width: usize) -> Result<(), Box<dyn Error>> {
+    if !(MIN_SIZE..=MAX_ROWS).contains(&height) || !(MIN_SIZE..=MAX_COLUMNS).contains(&width) {
+        return Err(format!(
+            "layouts have to be {} to {} rows by {} to {} columns",
+            MIN_SIZE, MAX_ROWS, MIN_SIZE, MAX_COLUMNS
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+*f1..
+23X..
+1F...
+.....
+.....
+";
+
+    #[test]
+    fn text_round_trip() {
+        let game = from_text(TEXT).unwrap();
+        assert_eq!((game.height, game.width, game.mines), (5, 5, 3));
+        // the first line is the top row
+        assert!(game.cells[4][0].mine);
+        assert!(game.cells[4][1].flagged() && !game.cells[4][1].mine);
+        assert!(game.cells[2][1].flagged() && game.cells[2][1].mine);
+        assert_eq!(game.state, GameState::Lost);
+        assert_eq!(to_text(&game), TEXT);
+    }
+
+    #[test]
+    fn opening_every_safe_cell_wins() {
+        let game = from_text("\n  *1000\n\n  11000\n00000\n00000\n00000\n").unwrap();
+        assert_eq!(game.state, GameState::Won);
+        assert_eq!(to_text(&game), "*1000\n11000\n00000\n00000\n00000\n");
+    }
+
+    #[test]
+    fn broken_text_is_refused() {
+        assert!(from_text("").is_err());
+        assert!(from_text("\n\n").is_err());
+        // the broken rows go on top of an otherwise fine board
+        let rows = "\n.....\n.....\n.....\n.....\n";
+        for broken in ["*....\n....", "*...?", "*2...", "*....\n.0..."] {
+            let broken = format!("{}{}", broken, rows);
+            assert!(from_text(&broken).is_err(), "{:?}", broken);
+        }
+    }
+
+    #[test]
+    fn mbf_round_trip() {
+        let game = from_text("*....\n.....\n.....\n.....\n.**..\n").unwrap();
+        let bytes = to_mbf(&game).unwrap();
+        // the mines are counted from the top left
+        assert_eq!(bytes, vec![5, 5, 0, 3, 1, 4, 2, 4, 0, 0]);
+        let loaded = from_mbf(&bytes).unwrap();
+        assert_eq!(to_text(&loaded), to_text(&game));
+    }
+
+    #[test]
+    fn broken_mbf_is_refused() {
+        for broken in [
+            &[5, 5, 0][..],
+            &[0, 5, 0, 0],
+            &[5, 5, 0, 2, 0, 0],
+            &[5, 5, 0, 1, 5, 0],
+            &[5, 5, 0, 2, 1, 1, 1, 1],
+        ] {
+            assert!(from_mbf(broken).is_err(), "{:?}", broken);
+        }
+    }
+
+    #[test]
+    fn layouts_have_to_fit_the_board_sizes() {
+        let row = ".".repeat(MAX_COLUMNS + 1);
+        let wide = format!("{}\n", row).repeat(MIN_SIZE);
+        let tall = ".....\n".repeat(MAX_ROWS + 1);
+        for text in [
+            wide.as_str(),
+            tall.as_str(),
+            "*...\n....\n....\n....\n....\n",
+        ] {
+            assert!(from_text(text).is_err(), "{:?}", text);
+        }
+        assert!(from_text(&".....\n".repeat(MAX_ROWS)).is_ok());
+
+        for bytes in [
+            &[MAX_COLUMNS as u8 + 1, 5, 0, 0][..],
+            &[5, MAX_ROWS as u8 + 1, 0, 0],
+            &[255, 255, 0, 0],
+            &[4, 5, 0, 0],
+        ] {
+            assert!(from_mbf(bytes).is_err(), "{:?}", bytes);
+        }
+        assert!(from_mbf(&[MAX_COLUMNS as u8, MAX_ROWS as u8, 0, 0]).is_ok());
+    }
+
+    #[test]
+    fn mbf_holds_boards_up_to_255_across() {
+        assert!(to_mbf(&Game::with_mines(1, 256, &[(0, 0)])).is_err());
+    }
+}
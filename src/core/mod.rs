@@ -0,0 +1,14 @@
+//! The rules of minesweeper, free of any bevy types so they can be driven from tests, bots or a
+//! terminal just as easily as from the game itself.
+pub mod bot;
+mod cell;
+mod game;
+mod history;
+pub mod layout;
+pub mod solver;
+mod stats;
+
+pub use cell::*;
+pub use game::*;
+pub use history::*;
+pub use stats::*;
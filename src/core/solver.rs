@@ -0,0 +1,710 @@
+use super::game::{Game, GameState};
+
+use std::collections::HashMap;
+
+/// how many cells a search of the frontier may try before giving up on a part of it, which keeps
+/// deductions quick enough to run on every attempt while generating no-guess boards
+const MAX_SEARCH_STEPS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduction {
+    Safe((usize, usize)),
+    Mine((usize, usize)),
+}
+
+/// `mines` of the unopened `cells` around an opened cell are mines
+struct Constraint {
+    cells: Vec<(usize, usize)>,
+    mines: usize,
+}
+
+/// A connected part of the frontier and every way its mines could be laid out.
+struct Component {
+    cells: Vec<(usize, usize)>,
+    /// indexed by the number of mines used: how many layouts use that many, and how many of those
+    /// have a mine in each of the cells
+    layouts: Vec<(u64, Vec<u64>)>,
+}
+
+/// every unopened cell that the opened numbers and the total mine count prove to be safe or a
+/// mine, using the rules a person would, from single numbers up to trying every layout of the
+/// cells next to opened ones. only what can be seen is used, and flags are ignored since they may
+/// be wrong
+pub fn deduce(game: &Game) -> Vec<Deduction> {
+    let mut deductions = vec![];
+    for (row, cells) in known_cells(game).into_iter().enumerate() {
+        for (col, cell) in cells.into_iter().enumerate() {
+            match cell {
+                Some(true) => deductions.push(Deduction::Mine((row, col))),
+                Some(false) => deductions.push(Deduction::Safe((row, col))),
+                None => {}
+            }
+        }
+    }
+    deductions
+}
+
+/// the chance of each unopened cell being a mine, counting every layout of the mines that fits
+/// the numbers and the total mine count as equally likely. opened cells are `None`, and so is the
+/// whole board when the frontier is too big to search
+pub fn probabilities(game: &Game) -> Option<Vec<Vec<Option<f64>>>> {
+    let known = known_cells(game);
+    let (components, complete) = frontier(&constraints(game, &known));
+    if !complete {
+        return None;
+    }
+    let (unknown, mines_left) = unknown_cells(game, &known);
+    let frontier_size: usize = components
+        .iter()
+        .map(|component| component.cells.len())
+        .sum();
+    let interior = unknown.len() - frontier_size;
+
+    // the number of ways the interior can hold what the frontier leaves over grows far past what
+    // a float holds on big boards, so it is kept as a logarithm and scaled down before use
+    let mut ln_ways = vec![0.0; interior.min(mines_left) + 1];
+    for rest in 1..ln_ways.len() {
+        ln_ways[rest] = ln_ways[rest - 1] + ((interior - rest + 1) as f64 / rest as f64).ln();
+    }
+    let interior_ways = |total: usize| {
+        mines_left
+            .checked_sub(total)
+            .and_then(|rest| ln_ways.get(rest).copied())
+    };
+    let all = weighted_totals(components.iter(), mines_left);
+    let scale = (0..=mines_left)
+        .filter(|total| all[*total] > 0.0)
+        .filter_map(interior_ways)
+        .fold(f64::MIN, f64::max);
+    // layouts of the whole board with `extra` more mines on top of the given totals
+    let weight = |totals: &[f64], extra: usize| -> f64 {
+        totals
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0.0)
+            .filter_map(|(total, count)| {
+                Some(count * (interior_ways(total + extra)? - scale).exp())
+            })
+            .sum()
+    };
+    let layouts = weight(&all, 0);
+    if layouts <= 0.0 {
+        return None;
+    }
+
+    let mut chances: Vec<Vec<Option<f64>>> = known
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.map(|mine| mine as u8 as f64))
+                .collect()
+        })
+        .collect();
+    for (index, component) in components.iter().enumerate() {
+        let others = weighted_totals(
+            components
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, component)| component),
+            mines_left,
+        );
+        let mut mines = vec![0.0; component.cells.len()];
+        for (placed, (count, cell_mines)) in component.layouts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let ways = weight(&others, placed);
+            for (cell, cell_mines) in cell_mines.iter().enumerate() {
+                mines[cell] += *cell_mines as f64 * ways;
+            }
+        }
+        for (cell, (row, col)) in component.cells.iter().enumerate() {
+            chances[*row][*col] = Some(mines[cell] / layouts);
+        }
+    }
+
+    if interior > 0 {
+        let interior_mines: f64 = (0..=mines_left)
+            .filter(|total| all[*total] > 0.0)
+            .filter_map(|total| {
+                let ways = (interior_ways(total)? - scale).exp();
+                Some(all[total] * ways * (mines_left - total) as f64)
+            })
+            .sum();
+        let chance = interior_mines / interior as f64 / layouts;
+        for (row, col) in unknown {
+            if chances[row][col].is_none() {
+                chances[row][col] = Some(chance);
+            }
+        }
+    }
+
+    for cell in game.cells.iter().flatten().filter(|cell| cell.opened) {
+        chances[cell.row][cell.column] = None;
+    }
+    Some(chances)
+}
+
+/// `Some(true)` for every proven mine and `Some(false)` for every proven safe cell
+fn known_cells(game: &Game) -> Vec<Vec<Option<bool>>> {
+    let mut known: Vec<Vec<Option<bool>>> = vec![vec![None; game.width]; game.height];
+
+    loop {
+        let constraints = constraints(game, &known);
+        let mut progress = false;
+
+        for constraint in constraints.iter() {
+            if constraint.mines == 0 {
+                progress |= mark(&mut known, &constraint.cells, false);
+            } else if constraint.mines == constraint.cells.len() {
+                progress |= mark(&mut known, &constraint.cells, true);
+            }
+        }
+        if progress {
+            continue;
+        }
+
+        progress = subset_rule(game, &constraints, &mut known)
+            || total_rule(game, &mut known)
+            || enumeration_rule(game, &constraints, &mut known);
+        if !progress {
+            break;
+        }
+    }
+    known
+}
+
+/// one cell worth pointing out to a stuck player: a safe cell to open if there is one, otherwise a
+/// mine that hasn't been flagged yet
+pub fn hint(game: &Game) -> Option<Deduction> {
+    if game.is_over() {
+        return None;
+    }
+    let deductions = deduce(game);
+    deductions
+        .iter()
+        .find(|deduction| matches!(deduction, Deduction::Safe(_)))
+        .or_else(|| {
+            deductions.iter().find(|deduction| match deduction {
+                Deduction::Mine((row, col)) => !game.cells[*row][*col].flagged(),
+                Deduction::Safe(_) => false,
+            })
+        })
+        .copied()
+}
+
+/// whether the board can be cleared from `start` by deduction alone, without ever guessing
+pub fn solvable(game: &Game, start: (usize, usize)) -> bool {
+    let mut game = game.clone();
+    if game.reveal(start).is_err() {
+        return false;
+    }
+
+    while game.state == GameState::Playing {
+        let safe: Vec<(usize, usize)> = deduce(&game)
+            .into_iter()
+            .filter_map(|deduction| match deduction {
+                Deduction::Safe(position) => Some(position),
+                Deduction::Mine(_) => None,
+            })
+            .collect();
+        if safe.is_empty() {
+            return false;
+        }
+        for position in safe {
+            if game.reveal(position).is_err() {
+                return false;
+            }
+        }
+    }
+    game.state == GameState::Won
+}
+
+fn constraints(game: &Game, known: &[Vec<Option<bool>>]) -> Vec<Constraint> {
+    let mut constraints = vec![];
+    for row in game.cells.iter() {
+        for cell in row.iter().filter(|cell| cell.opened) {
+            let mut cells = vec![];
+            let mut mines = cell.value as usize;
+            for (r, c) in game.neighbours((cell.row, cell.column)) {
+                if game.cells[r][c].opened {
+                    continue;
+                }
+                match known[r][c] {
+                    Some(true) => mines -= 1,
+                    Some(false) => {}
+                    None => cells.push((r, c)),
+                }
+            }
+            if !cells.is_empty() {
+                constraints.push(Constraint { cells, mines });
+            }
+        }
+    }
+    constraints
+}
+
+/// when every cell of one constraint is also part of another, the cells only the larger one
+/// touches hold the difference between their mines
+fn subset_rule(game: &Game, constraints: &[Constraint], known: &mut [Vec<Option<bool>>]) -> bool {
+    // only constraints sharing a cell can be subsets of each other
+    let mut touching: Vec<Vec<Vec<usize>>> = vec![vec![vec![]; game.width]; game.height];
+    for (index, constraint) in constraints.iter().enumerate() {
+        for (row, col) in constraint.cells.iter() {
+            touching[*row][*col].push(index);
+        }
+    }
+
+    let mut progress = false;
+    for small in constraints.iter() {
+        let (row, col) = small.cells[0];
+        for large in touching[row][col].iter().map(|index| &constraints[*index]) {
+            if large.cells.len() <= small.cells.len()
+                || !small.cells.iter().all(|cell| large.cells.contains(cell))
+            {
+                continue;
+            }
+            let rest: Vec<(usize, usize)> = large
+                .cells
+                .iter()
+                .filter(|cell| !small.cells.contains(*cell))
+                .copied()
+                .collect();
+            let mines = large.mines - small.mines;
+            if mines == 0 {
+                progress |= mark(known, &rest, false);
+            } else if mines == rest.len() {
+                progress |= mark(known, &rest, true);
+            }
+        }
+    }
+    progress
+}
+
+/// once every mine is accounted for the rest of the board is safe, and once the unknown cells
+/// are all that is left for the remaining mines they must all be mines
+fn total_rule(game: &Game, known: &mut [Vec<Option<bool>>]) -> bool {
+    let (unknown, mines_left) = unknown_cells(game, known);
+    if unknown.is_empty() {
+        false
+    } else if mines_left == 0 {
+        mark(known, &unknown, false)
+    } else if mines_left == unknown.len() {
+        mark(known, &unknown, true)
+    } else {
+        false
+    }
+}
+
+/// tries every layout of the frontier that fits the numbers, counting the rest of the unknown cells
+/// and the total number of mines in. a cell that is a mine in every layout, or in none, is known
+fn enumeration_rule(
+    game: &Game,
+    constraints: &[Constraint],
+    known: &mut [Vec<Option<bool>>],
+) -> bool {
+    let (components, complete) = frontier(constraints);
+    let (unknown, mines_left) = unknown_cells(game, known);
+    let frontier_size: usize = components
+        .iter()
+        .map(|component| component.cells.len())
+        .sum();
+    let interior = unknown.len() - frontier_size;
+
+    let mut progress = false;
+    for (index, component) in components.iter().enumerate() {
+        let others = mine_totals(
+            components
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, component)| component),
+            mines_left,
+        );
+        let feasible: Vec<&(u64, Vec<u64>)> = component
+            .layouts
+            .iter()
+            .enumerate()
+            .filter(|(mines, (count, _))| {
+                // without every part of the frontier the total can't be relied on
+                *count > 0 && (!complete || fits(&others, mines_left.checked_sub(*mines), interior))
+            })
+            .map(|(_, layouts)| layouts)
+            .collect();
+        if feasible.is_empty() {
+            continue;
+        }
+
+        for (cell, position) in component.cells.iter().enumerate() {
+            if feasible.iter().all(|(_, mines)| mines[cell] == 0) {
+                progress |= mark(known, &[*position], false);
+            } else if feasible.iter().all(|(count, mines)| mines[cell] == *count) {
+                progress |= mark(known, &[*position], true);
+            }
+        }
+    }
+
+    // the cells away from the numbers get whatever mines the frontier leaves over
+    let totals = mine_totals(components.iter(), mines_left);
+    let interior_mines: Vec<usize> = (0..=mines_left)
+        .filter(|total| totals[*total] && mines_left - total <= interior)
+        .map(|total| mines_left - total)
+        .collect();
+    if complete && interior > 0 && !interior_mines.is_empty() {
+        let interior_cells: Vec<(usize, usize)> = unknown
+            .into_iter()
+            .filter(|(row, col)| {
+                !components
+                    .iter()
+                    .any(|component| component.cells.contains(&(*row, *col)))
+            })
+            .collect();
+        if interior_mines.iter().all(|mines| *mines == 0) {
+            progress |= mark(known, &interior_cells, false);
+        } else if interior_mines.iter().all(|mines| *mines == interior) {
+            progress |= mark(known, &interior_cells, true);
+        }
+    }
+    progress
+}
+
+/// the unknown unopened cells and how many mines are among them
+fn unknown_cells(game: &Game, known: &[Vec<Option<bool>>]) -> (Vec<(usize, usize)>, usize) {
+    let mut unknown = vec![];
+    let mut mines_found = 0;
+    for (row, cells) in game.cells.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            if cell.opened {
+                continue;
+            }
+            match known[row][col] {
+                Some(true) => mines_found += 1,
+                Some(false) => {}
+                None => unknown.push((row, col)),
+            }
+        }
+    }
+    (unknown, (game.mines as usize).saturating_sub(mines_found))
+}
+
+/// whether some number of mines in the other components leaves between none and all of the
+/// interior cells for the rest
+fn fits(others: &[bool], mines_left: Option<usize>, interior: usize) -> bool {
+    let mines_left = match mines_left {
+        Some(mines_left) => mines_left,
+        None => return false,
+    };
+    (0..=mines_left).any(|total| others[total] && mines_left - total <= interior)
+}
+
+/// which totals up to `limit` the components' mines can add up to
+fn mine_totals<'a>(components: impl Iterator<Item = &'a Component>, limit: usize) -> Vec<bool> {
+    let mut totals = vec![false; limit + 1];
+    totals[0] = true;
+    for component in components {
+        let mut next = vec![false; limit + 1];
+        for (total, _) in totals
+            .iter()
+            .enumerate()
+            .filter(|(_, reachable)| **reachable)
+        {
+            for (mines, (count, _)) in component.layouts.iter().enumerate() {
+                if *count > 0 && total + mines <= limit {
+                    next[total + mines] = true;
+                }
+            }
+        }
+        totals = next;
+    }
+    totals
+}
+
+/// how many layouts of the components add up to each total up to `limit`
+fn weighted_totals<'a>(components: impl Iterator<Item = &'a Component>, limit: usize) -> Vec<f64> {
+    let mut totals = vec![0.0; limit + 1];
+    totals[0] = 1.0;
+    for component in components {
+        let mut next = vec![0.0; limit + 1];
+        for (total, ways) in totals.iter().enumerate().filter(|(_, ways)| **ways > 0.0) {
+            for (mines, (count, _)) in component.layouts.iter().enumerate() {
+                if *count > 0 && total + mines <= limit {
+                    next[total + mines] += ways * *count as f64;
+                }
+            }
+        }
+        totals = next;
+    }
+    totals
+}
+
+/// splits the frontier into parts that don't share any numbers and lays each of them out, leaving
+/// out parts too big to search. the flag is false when any were left out
+fn frontier(constraints: &[Constraint]) -> (Vec<Component>, bool) {
+    let mut by_cell: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for cell in constraint.cells.iter() {
+            by_cell.entry(*cell).or_default().push(index);
+        }
+    }
+
+    let mut components = vec![];
+    let mut complete = true;
+    let mut visited = vec![false; constraints.len()];
+    for start in 0..constraints.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+
+        // cells are added in the order they are reached so neighbouring cells are decided
+        // together, which lets the search rule out bad layouts early
+        let mut queue = vec![start];
+        let mut members = vec![];
+        let mut cells = vec![];
+        while let Some(index) = queue.pop() {
+            members.push(index);
+            for cell in constraints[index].cells.iter() {
+                if cells.contains(cell) {
+                    continue;
+                }
+                cells.push(*cell);
+                for other in by_cell[cell].iter() {
+                    if !visited[*other] {
+                        visited[*other] = true;
+                        queue.push(*other);
+                    }
+                }
+            }
+        }
+
+        let members: Vec<&Constraint> = members.iter().map(|index| &constraints[*index]).collect();
+        match Search::new(cells, &members).run() {
+            Some(component) => components.push(component),
+            None => complete = false,
+        }
+    }
+    (components, complete)
+}
+
+/// a depth first search over the mine or no mine choices for the cells of one component
+struct Search {
+    cells: Vec<(usize, usize)>,
+    /// the constraints each cell is part of
+    cell_constraints: Vec<Vec<usize>>,
+    /// for each constraint, the mines it needs, the mines placed so far and the cells still to
+    /// decide
+    constraints: Vec<(usize, usize, usize)>,
+    layouts: Vec<(u64, Vec<u64>)>,
+    mines: Vec<bool>,
+    steps: usize,
+}
+
+impl Search {
+    fn new(cells: Vec<(usize, usize)>, constraints: &[&Constraint]) -> Self {
+        let mut cell_constraints = vec![vec![]; cells.len()];
+        for (index, constraint) in constraints.iter().enumerate() {
+            for cell in constraint.cells.iter() {
+                let position = cells.iter().position(|other| other == cell).unwrap();
+                cell_constraints[position].push(index);
+            }
+        }
+
+        Search {
+            cell_constraints,
+            constraints: constraints
+                .iter()
+                .map(|constraint| (constraint.mines, 0, constraint.cells.len()))
+                .collect(),
+            layouts: vec![(0, vec![0; cells.len()]); cells.len() + 1],
+            mines: vec![false; cells.len()],
+            steps: 0,
+            cells,
+        }
+    }
+
+    fn run(mut self) -> Option<Component> {
+        if !self.search(0, 0) {
+            return None;
+        }
+        Some(Component {
+            cells: self.cells,
+            layouts: self.layouts,
+        })
+    }
+
+    /// decides cell `next` onwards with `placed` mines already down. false once the search has
+    /// taken too many steps
+    fn search(&mut self, next: usize, placed: usize) -> bool {
+        if next == self.cells.len() {
+            let (count, mines) = &mut self.layouts[placed];
+            *count += 1;
+            for (cell, mine) in self.mines.iter().enumerate() {
+                mines[cell] += *mine as u64;
+            }
+            return true;
+        }
+
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return false;
+        }
+
+        for mine in [false, true] {
+            let mut fits = true;
+            for index in self.cell_constraints[next].iter() {
+                let (needed, placed, undecided) = &mut self.constraints[*index];
+                *placed += mine as usize;
+                *undecided -= 1;
+                fits &= *placed <= *needed && *placed + *undecided >= *needed;
+            }
+
+            self.mines[next] = mine;
+            let finished = !fits || self.search(next + 1, placed + mine as usize);
+
+            for index in self.cell_constraints[next].iter() {
+                let (_, placed, undecided) = &mut self.constraints[*index];
+                *placed -= mine as usize;
+                *undecided += 1;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        self.mines[next] = false;
+        true
+    }
+}
+
+fn mark(known: &mut [Vec<Option<bool>>], cells: &[(usize, usize)], mine: bool) -> bool {
+    let mut progress = false;
+    for (row, col) in cells.iter() {
+        if known[*row][*col].is_none() {
+            known[*row][*col] = Some(mine);
+            progress = true;
+        }
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Mark;
+
+    /// a board with the mines laid out and only the given cells opened, without flooding
+    fn board(
+        rows: usize,
+        columns: usize,
+        mines: &[(usize, usize)],
+        opened: &[(usize, usize)],
+    ) -> Game {
+        let mut game = Game::with_mines(rows, columns, mines);
+        for (row, col) in opened {
+            game.cells[*row][*col].opened = true;
+            game.cells_unopened -= 1;
+        }
+        game
+    }
+
+    /// the 1-2-1 pattern: a mine in the middle would leave the 2 short
+    fn one_two_one() -> Game {
+        board(2, 3, &[(0, 0), (0, 2)], &[(1, 0), (1, 1), (1, 2)])
+    }
+
+    #[test]
+    fn deduces_the_one_two_one() {
+        assert_eq!(
+            deduce(&one_two_one()),
+            vec![
+                Deduction::Mine((0, 0)),
+                Deduction::Safe((0, 1)),
+                Deduction::Mine((0, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zeros_prove_their_neighbours_safe() {
+        let game = board(3, 3, &[(0, 0)], &[(1, 1), (2, 2)]);
+        assert_eq!(
+            deduce(&game),
+            vec![Deduction::Safe((1, 2)), Deduction::Safe((2, 1))]
+        );
+    }
+
+    #[test]
+    fn hints_ignore_flags() {
+        let mut game = one_two_one();
+        game.cells[0][1].mark = Mark::Flag;
+        assert_eq!(hint(&game), Some(Deduction::Safe((0, 1))));
+
+        let mut game = board(2, 3, &[(0, 0), (0, 2)], &[(0, 1), (1, 0), (1, 1), (1, 2)]);
+        assert_eq!(hint(&game), Some(Deduction::Mine((0, 0))));
+        game.cells[0][0].mark = Mark::Flag;
+        assert_eq!(hint(&game), Some(Deduction::Mine((0, 2))));
+    }
+
+    #[test]
+    fn solvable_only_without_guesses() {
+        let game = Game::with_mines(1, 4, &[(0, 3)]);
+        assert!(solvable(&game, (0, 0)));
+        // the 1 in the corner could be next to any of the three other cells
+        let game = Game::with_mines(2, 2, &[(0, 0)]);
+        assert!(!solvable(&game, (1, 1)));
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn a_number_shares_its_mines_between_its_cells() {
+        let game = board(3, 3, &[(0, 0)], &[(1, 1), (2, 2)]);
+        let chances = probabilities(&game).unwrap();
+        for (row, col) in [(0, 0), (0, 1), (0, 2), (1, 0), (2, 0)] {
+            assert_close(chances[row][col], 0.2);
+        }
+        assert_close(chances[1][2], 0.0);
+        assert_close(chances[2][1], 0.0);
+        assert_eq!(chances[1][1], None);
+        assert_eq!(chances[2][2], None);
+    }
+
+    #[test]
+    fn proven_cells_are_certain() {
+        let chances = probabilities(&one_two_one()).unwrap();
+        assert_close(chances[0][0], 1.0);
+        assert_close(chances[0][1], 0.0);
+        assert_close(chances[0][2], 1.0);
+    }
+
+    #[test]
+    fn cells_away_from_the_numbers_share_the_mines_left() {
+        let game = board(1, 6, &[(0, 5)], &[(0, 0)]);
+        let chances = probabilities(&game).unwrap();
+        assert_close(chances[0][1], 0.0);
+        for chance in &chances[0][2..] {
+            assert_close(*chance, 0.25);
+        }
+    }
+
+    #[test]
+    fn the_mine_count_weighs_the_frontier() {
+        // the 1 holds exactly one of the two mines, so the other is in one of the two cells
+        // away from it
+        let game = board(2, 3, &[(0, 0), (1, 2)], &[(1, 0)]);
+        let chances = probabilities(&game).unwrap();
+        for (row, col) in [(0, 0), (0, 1), (1, 1)] {
+            assert_close(chances[row][col], 1.0 / 3.0);
+        }
+        assert_close(chances[0][2], 0.5);
+        assert_close(chances[1][2], 0.5);
+    }
+}
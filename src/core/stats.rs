@@ -0,0 +1,100 @@
+use super::game::Game;
+
+use std::time::Duration;
+
+/// Every click the player made on the board, including the ones that didn't change anything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clicks {
+    /// opening the cells around an opened one
+    pub chord: u32,
+    pub left: u32,
+    /// placing or taking away flags, one at a time or around an opened cell
+    pub right: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.chord + self.left + self.right
+    }
+}
+
+/// How quickly and how efficiently a game was played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameStats {
+    pub clicks: Clicks,
+    pub elapsed: Duration,
+    /// the part of the board's 3BV that was cleared, all of it for a won game
+    pub solved_three_bv: u32,
+    pub three_bv: u32,
+}
+
+impl GameStats {
+    /// the board has no 3BV until the mines are laid out on the first reveal
+    pub fn new(game: &Game, clicks: Clicks, elapsed: Duration) -> Self {
+        let (solved_three_bv, three_bv) = if game.initialized {
+            (game.solved_three_bv(), game.three_bv())
+        } else {
+            (0, 0)
+        };
+        GameStats {
+            clicks,
+            elapsed,
+            solved_three_bv,
+            three_bv,
+        }
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.solved_three_bv as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// the cleared 3BV for every click made. a game played without a wasted click scores 1, and
+    /// chords that open several cells at once can take it higher
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks.total() == 0 {
+            return 0.0;
+        }
+        self.solved_three_bv as f64 / self.clicks.total() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn efficiency_and_speed_of_a_won_game() {
+        let mut game = Game::with_mines(3, 3, &[(0, 0), (2, 2)]);
+        game.reveal((2, 0)).unwrap();
+        game.reveal((0, 2)).unwrap();
+        let clicks = Clicks {
+            chord: 0,
+            left: 2,
+            right: 2,
+        };
+        let stats = GameStats::new(&game, clicks, Duration::from_secs(4));
+        assert_eq!(stats.three_bv, 2);
+        assert_eq!(stats.solved_three_bv, 2);
+        assert_eq!(stats.efficiency(), 0.5);
+        assert_eq!(stats.three_bv_per_second(), 0.5);
+    }
+
+    #[test]
+    fn no_clicks_or_time_score_nothing() {
+        let game = Game::with_mines(3, 3, &[(0, 0), (2, 2)]);
+        let stats = GameStats::new(&game, Clicks::default(), Duration::ZERO);
+        assert_eq!(stats.efficiency(), 0.0);
+        assert_eq!(stats.three_bv_per_second(), 0.0);
+
+        // the mines aren't laid out before the first reveal
+        let stats = GameStats::new(
+            &Game::with_seed(9, 9, 10, 0),
+            Clicks::default(),
+            Duration::ZERO,
+        );
+        assert_eq!(stats.three_bv, 0);
+    }
+}
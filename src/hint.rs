@@ -0,0 +1,96 @@
+use crate::components::{BasicCell, Board, GameChangedEvent, CELL_SIZE, CELL_SPACING};
+use crate::core::solver::{self, Deduction};
+use crate::AppState;
+
+use bevy::prelude::*;
+
+const SAFE_COLOR: Color = Color::rgba(0.2, 0.9, 0.3, 0.6);
+const MINE_COLOR: Color = Color::rgba(0.95, 0.2, 0.2, 0.6);
+
+/// asks the solver for one cell that can be worked out from what is showing
+pub struct HintEvent;
+
+/// covers the hinted cell until the board changes
+#[derive(Component)]
+pub struct HintMarker;
+
+pub fn spawn_hint_marker(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(
+                    CELL_SIZE + CELL_SPACING,
+                    CELL_SIZE + CELL_SPACING,
+                )),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(HintMarker);
+}
+
+/// ranked games are played without help
+pub fn show_hint(
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut ev_hint: EventReader<HintEvent>,
+    mut marker_query: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<HintMarker>>,
+) {
+    if ev_hint.iter().count() == 0 {
+        return;
+    }
+    let board = board_query.single();
+    if board.ranked {
+        info!("hints are off in ranked games");
+        return;
+    }
+
+    let (position, color) = match solver::hint(&board.game) {
+        Some(Deduction::Safe(position)) => (position, SAFE_COLOR),
+        Some(Deduction::Mine(position)) => (position, MINE_COLOR),
+        None => {
+            info!("nothing can be worked out from here without guessing");
+            return;
+        }
+    };
+    let basic_cell = if let Some(basic_cell) = cell_query
+        .iter()
+        .find(|basic_cell| (basic_cell.row, basic_cell.column) == position)
+    {
+        basic_cell
+    } else {
+        return;
+    };
+
+    let (mut sprite, mut transform, mut visibility) = marker_query.single_mut();
+    sprite.color = color;
+    transform.translation = basic_cell.position.truncate().extend(3.0);
+    visibility.is_visible = true;
+}
+
+/// a hint is only good for the board it was given on
+pub fn hide_hint(
+    new_board_query: Query<&Board, Added<Board>>,
+    mut ev_changed: EventReader<GameChangedEvent>,
+    mut marker_query: Query<&mut Visibility, With<HintMarker>>,
+) {
+    if ev_changed.iter().count() == 0 && new_board_query.iter().next().is_none() {
+        return;
+    }
+    marker_query.single_mut().is_visible = false;
+}
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HintEvent>();
+        app.add_startup_system(spawn_hint_marker);
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(hide_hint.label("hide_hint").after("apply_game_changes"))
+                .with_system(show_hint.after("hide_hint")),
+        );
+    }
+}
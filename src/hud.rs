@@ -0,0 +1,86 @@
+use crate::components::Board;
+use crate::menu::{text, FONT};
+use crate::playback::Playback;
+use crate::AppState;
+
+use bevy::prelude::*;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum HudText {
+    MinesLeft,
+    /// how a replay is being played back, empty otherwise
+    Playback,
+    Timer,
+}
+
+fn spawn_hud(asset_server: Res<AssetServer>, mut commands: Commands) {
+    let font: Handle<Font> = asset_server.load(FONT);
+
+    for (hud_text, position) in [
+        (
+            HudText::MinesLeft,
+            Rect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..Default::default()
+            },
+        ),
+        (
+            HudText::Playback,
+            Rect {
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..Default::default()
+            },
+        ),
+        (
+            HudText::Timer,
+            Rect {
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..Default::default()
+            },
+        ),
+    ] {
+        let mut bundle = text("", font.clone(), 32.0, Color::MIDNIGHT_BLUE);
+        bundle.style.position_type = PositionType::Absolute;
+        bundle.style.position = position;
+        commands.spawn_bundle(bundle).insert(hud_text);
+    }
+}
+
+fn despawn_hud(mut commands: Commands, hud_query: Query<Entity, With<HudText>>) {
+    for entity in hud_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_hud(
+    board_query: Query<&Board>,
+    mut hud_query: Query<(&HudText, &mut Text)>,
+    playback: Res<Playback>,
+) {
+    let board = board_query.single();
+    for (hud_text, mut text) in hud_query.iter_mut() {
+        text.sections[0].value = match hud_text {
+            HudText::MinesLeft => format!("{:03}", board.mines_left()),
+            HudText::Playback if !playback.is_active() => String::new(),
+            HudText::Playback if playback.is_finished() => "replay over".to_string(),
+            HudText::Playback if playback.paused => "replay paused".to_string(),
+            HudText::Playback => format!("replay {}x", playback.speed),
+            HudText::Timer => format!("{:03}", board.elapsed.as_secs()),
+        };
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::InGame).with_system(spawn_hud));
+        app.add_system_set(SystemSet::on_resume(AppState::InGame).with_system(spawn_hud));
+        app.add_system_set(SystemSet::on_update(AppState::InGame).with_system(update_hud));
+        app.add_system_set(SystemSet::on_pause(AppState::InGame).with_system(despawn_hud));
+        app.add_system_set(SystemSet::on_exit(AppState::InGame).with_system(despawn_hud));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::components::{
+    ActionEvent, BasicCell, Board, LoadGameEvent, NewGameEvent, CELL_SIZE, CELL_SPACING,
+};
+use crate::core::{layout, Clicks};
+use crate::hint::HintEvent;
+use crate::overlay::ToggleOverlayEvent;
+use crate::playback::Playback;
+use crate::replay::Action;
+use crate::save::SavedGame;
+use crate::storage::{PlatformStorage, Storage};
+use crate::AppState;
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+const CURSOR_COLOR: Color = Color::rgba(1.0, 0.75, 0.8, 0.6);
+
+const UP: [KeyCode; 3] = [KeyCode::Up, KeyCode::W, KeyCode::K];
+const DOWN: [KeyCode; 3] = [KeyCode::Down, KeyCode::S, KeyCode::J];
+const LEFT: [KeyCode; 3] = [KeyCode::Left, KeyCode::A, KeyCode::H];
+const RIGHT: [KeyCode; 3] = [KeyCode::Right, KeyCode::D, KeyCode::L];
+const REVEAL: [KeyCode; 3] = [KeyCode::Space, KeyCode::Return, KeyCode::NumpadEnter];
+const FLAG: [KeyCode; 1] = [KeyCode::F];
+// D already moves right with WASD, so chording gets its own key
+const CHORD: [KeyCode; 1] = [KeyCode::C];
+// H moves left with the vim keys
+const HINT: [KeyCode; 1] = [KeyCode::T];
+const OVERLAY: [KeyCode; 1] = [KeyCode::P];
+// command rather than control is used for undo on macs
+const CONTROL: [KeyCode; 4] = [
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LWin,
+    KeyCode::RWin,
+];
+const SHIFT: [KeyCode; 2] = [KeyCode::LShift, KeyCode::RShift];
+
+/// where ctrl+e writes the layout of the board and ctrl+o reads one from
+const LAYOUT_KEY: &str = "layout";
+
+/// the cell keyboard moves act on. it stays hidden until a key is used so it doesn't get in the
+/// way of playing with the mouse
+#[derive(Component)]
+pub struct KeyboardCursor {
+    pub column: usize,
+    pub row: usize,
+}
+
+fn any_pressed(keyboard_input: &Input<KeyCode>, keys: &[KeyCode]) -> bool {
+    keys.iter().any(|key| keyboard_input.pressed(*key))
+}
+
+fn any_just_pressed(keyboard_input: &Input<KeyCode>, keys: &[KeyCode]) -> bool {
+    keys.iter().any(|key| keyboard_input.just_pressed(*key))
+}
+
+pub fn spawn_cursor(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: CURSOR_COLOR,
+                custom_size: Some(Vec2::new(
+                    CELL_SIZE + CELL_SPACING,
+                    CELL_SIZE + CELL_SPACING,
+                )),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(KeyboardCursor { column: 0, row: 0 });
+}
+
+pub fn move_cursor(
+    board_query: Query<&Board>,
+    mut cursor_query: Query<(&mut KeyboardCursor, &mut Visibility)>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    let board = board_query.single();
+    let (mut cursor, mut visibility) = cursor_query.single_mut();
+
+    let (row, column) = (cursor.row as isize, cursor.column as isize);
+    // row 0 is at the bottom of the board
+    let (row, column) = if any_just_pressed(&keyboard_input, &UP) {
+        (row + 1, column)
+    } else if any_just_pressed(&keyboard_input, &DOWN) {
+        (row - 1, column)
+    } else if any_just_pressed(&keyboard_input, &LEFT) {
+        (row, column - 1)
+    } else if any_just_pressed(&keyboard_input, &RIGHT) {
+        (row, column + 1)
+    } else {
+        return;
+    };
+
+    // the first press only shows where the cursor is
+    if visibility.is_visible {
+        cursor.row = row.clamp(0, board.game.height as isize - 1) as usize;
+        cursor.column = column.clamp(0, board.game.width as isize - 1) as usize;
+    }
+    visibility.is_visible = true;
+}
+
+pub fn place_cursor(
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut cursor_query: Query<(&mut KeyboardCursor, &mut Transform)>,
+) {
+    let board = board_query.single();
+    let (mut cursor, mut transform) = cursor_query.single_mut();
+
+    // a new game can be smaller than the last one
+    cursor.row = cursor.row.min(board.game.height - 1);
+    cursor.column = cursor.column.min(board.game.width - 1);
+
+    if let Some(basic_cell) = cell_query
+        .iter()
+        .find(|basic_cell| basic_cell.row == cursor.row && basic_cell.column == cursor.column)
+    {
+        transform.translation = basic_cell.position.truncate().extend(2.0);
+    }
+}
+
+pub fn keyboard_moves(
+    board_query: Query<&Board>,
+    cursor_query: Query<(&KeyboardCursor, &Visibility)>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_action: EventWriter<ActionEvent>,
+    playback: Res<Playback>,
+) {
+    let board = board_query.single();
+    let (cursor, visibility) = cursor_query.single();
+    if board.game.is_over() || !visibility.is_visible || playback.is_active() {
+        return;
+    }
+
+    let position = (cursor.row, cursor.column);
+    if position.0 >= board.game.height || position.1 >= board.game.width {
+        return;
+    }
+    let opened = board.game.cells[position.0][position.1].opened;
+
+    if any_just_pressed(&keyboard_input, &REVEAL) {
+        if opened {
+            ev_action.send(ActionEvent(Action::Chord(position)));
+        } else {
+            ev_action.send(ActionEvent(Action::Reveal(position)));
+        }
+    } else if any_just_pressed(&keyboard_input, &FLAG) {
+        if opened {
+            ev_action.send(ActionEvent(Action::AutoFlag(position)));
+        } else {
+            ev_action.send(ActionEvent(Action::ToggleFlag(position)));
+        }
+    } else if any_just_pressed(&keyboard_input, &CHORD) {
+        ev_action.send(ActionEvent(Action::Chord(position)));
+    }
+}
+
+pub fn hint_key(keyboard_input: Res<Input<KeyCode>>, mut ev_hint: EventWriter<HintEvent>) {
+    if any_just_pressed(&keyboard_input, &HINT) {
+        ev_hint.send(HintEvent);
+    }
+}
+
+pub fn overlay_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_toggle_overlay: EventWriter<ToggleOverlayEvent>,
+) {
+    if any_just_pressed(&keyboard_input, &OVERLAY) {
+        ev_toggle_overlay.send(ToggleOverlayEvent);
+    }
+}
+
+pub fn new_game_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_new_game: EventWriter<NewGameEvent>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        ev_new_game.send(NewGameEvent);
+    }
+}
+
+/// ctrl+z undoes, ctrl+y or ctrl+shift+z redoes. ranked games can't be taken back
+pub fn undo_keys(
+    board_query: Query<&Board>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_action: EventWriter<ActionEvent>,
+    playback: Res<Playback>,
+) {
+    if !any_pressed(&keyboard_input, &CONTROL) || playback.is_active() {
+        return;
+    }
+    if board_query.single().ranked {
+        return;
+    }
+
+    let shift = any_pressed(&keyboard_input, &SHIFT);
+    if keyboard_input.just_pressed(KeyCode::Y) || shift && keyboard_input.just_pressed(KeyCode::Z) {
+        ev_action.send(ActionEvent(Action::Redo));
+    } else if keyboard_input.just_pressed(KeyCode::Z) {
+        ev_action.send(ActionEvent(Action::Undo));
+    }
+}
+
+/// ctrl+e exports the layout of the board as a text grid once the game is over, and ctrl+o plays
+/// the exported layout, which may have been edited by hand since
+pub fn layout_keys(
+    board_query: Query<&Board>,
+    mut ev_load_game: EventWriter<LoadGameEvent>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut storage: ResMut<PlatformStorage>,
+) {
+    if !any_pressed(&keyboard_input, &CONTROL) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::E) {
+        let board = board_query.single();
+        if !board.game.is_over() {
+            info!("finish the game before exporting its layout");
+            return;
+        }
+        match storage.save(LAYOUT_KEY, &layout::to_text(&board.game)) {
+            Ok(()) => info!("exported the layout of the board"),
+            Err(err) => warn!("could not export the layout: {}", err),
+        }
+    } else if keyboard_input.just_pressed(KeyCode::O) {
+        let game = match storage
+            .load(LAYOUT_KEY)
+            .map(|text| layout::from_text(&text))
+        {
+            Some(Ok(game)) => game,
+            Some(Err(err)) => {
+                warn!("could not read the layout: {}", err);
+                return;
+            }
+            None => {
+                info!("there is no layout to import, export one with ctrl+e first");
+                return;
+            }
+        };
+        ev_load_game.send(LoadGameEvent(SavedGame {
+            clicks: Clicks::default(),
+            elapsed: Duration::ZERO,
+            game,
+            imported: true,
+            ranked: false,
+            undos: 0,
+        }));
+    }
+}
+
+pub fn pause_key(mut keyboard_input: ResMut<Input<KeyCode>>, mut state: ResMut<State<AppState>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        // the pause menu would otherwise see the same press and resume straight away
+        keyboard_input.reset(KeyCode::Escape);
+        if let Err(err) = state.push(AppState::Paused) {
+            warn!("could not pause the game: {:?}", err);
+        }
+    }
+}
+
+pub struct InGameKeyboardPlugin;
+
+impl Plugin for InGameKeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_cursor);
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(move_cursor.label("move_cursor"))
+                .with_system(place_cursor.after("move_cursor"))
+                .with_system(keyboard_moves.label("keyboard").after("move_cursor"))
+                .with_system(undo_keys.label("keyboard"))
+                .with_system(layout_keys)
+                .with_system(hint_key)
+                .with_system(overlay_key)
+                .with_system(new_game_key)
+                .with_system(pause_key),
+        );
+    }
+}
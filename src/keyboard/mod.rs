@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+
+mod in_game_keyboard_handlers;
+use in_game_keyboard_handlers::InGameKeyboardPlugin;
+
+pub struct KeyboardPlugin;
+
+impl Plugin for KeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InGameKeyboardPlugin);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::core::Game;
+use crate::storage::Storage;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::time::Duration;
+
+const LEADERBOARD_SIZE: usize = 10;
+const STORAGE_KEY: &str = "leaderboard";
+const VERSION: &str = "v1";
+
+/// Boards are only comparable when they have the same size and number of mines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Difficulty {
+    pub columns: usize,
+    pub mines: u16,
+    pub rows: usize,
+}
+
+impl From<&Game> for Difficulty {
+    fn from(game: &Game) -> Self {
+        Difficulty {
+            columns: game.width,
+            mines: game.mines,
+            rows: game.height,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    /// every click made during the game
+    pub clicks: u32,
+    /// seconds since the unix epoch
+    pub date: u64,
+    pub three_bv: u32,
+    pub time: Duration,
+    /// moves taken back during the game
+    pub undos: u32,
+}
+
+impl Entry {
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.time.is_zero() {
+            return 0.0;
+        }
+        self.three_bv as f64 / self.time.as_secs_f64()
+    }
+
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks == 0 {
+            return 0.0;
+        }
+        self.three_bv as f64 / self.clicks as f64
+    }
+}
+
+/// The fastest wins for every difficulty that has been won at least once.
+#[derive(Debug, Default)]
+pub struct Leaderboard {
+    entries: BTreeMap<Difficulty, Vec<Entry>>,
+}
+
+impl Leaderboard {
+    pub fn best(&self, difficulty: Difficulty) -> &[Entry] {
+        self.entries
+            .get(&difficulty)
+            .map(|entries| entries.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// adds the entry if it is fast enough, returning its place on the board. wins with fewer
+    /// undos always come first so that taking moves back can't beat a clean game
+    pub fn record(&mut self, difficulty: Difficulty, entry: Entry) -> Option<usize> {
+        let entries = self.entries.entry(difficulty).or_default();
+        let place = entries
+            .iter()
+            .position(|e| (entry.undos, entry.time) < (e.undos, e.time));
+        let place = match place {
+            Some(place) => place,
+            None if entries.len() < LEADERBOARD_SIZE => entries.len(),
+            None => return None,
+        };
+        entries.insert(place, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(place)
+    }
+
+    /// one line per entry of `rows columns mines milliseconds date undos three_bv clicks`
+    pub fn serialize(&self) -> String {
+        let mut out = format!("{}\n", VERSION);
+        for (difficulty, entries) in self.entries.iter() {
+            for entry in entries {
+                out.push_str(&format!(
+                    "{} {} {} {} {} {} {} {}\n",
+                    difficulty.rows,
+                    difficulty.columns,
+                    difficulty.mines,
+                    entry.time.as_millis(),
+                    entry.date,
+                    entry.undos,
+                    entry.three_bv,
+                    entry.clicks
+                ));
+            }
+        }
+        out
+    }
+
+    /// lines that can't be read are skipped rather than throwing the whole board away
+    pub fn deserialize(value: &str) -> Self {
+        let mut leaderboard = Leaderboard::default();
+        let mut lines = value.lines();
+        if lines.next() != Some(VERSION) {
+            return leaderboard;
+        }
+
+        for line in lines {
+            let fields: Vec<u64> =
+                if let Ok(fields) = line.split_whitespace().map(|field| field.parse()).collect() {
+                    fields
+                } else {
+                    continue;
+                };
+            let (rows, columns, mines, millis, date, undos, three_bv, clicks) = match fields[..] {
+                [rows, columns, mines, millis, date, undos, three_bv, clicks] => {
+                    (rows, columns, mines, millis, date, undos, three_bv, clicks)
+                }
+                _ => continue,
+            };
+            let difficulty = Difficulty {
+                columns: columns as usize,
+                mines: mines as u16,
+                rows: rows as usize,
+            };
+            leaderboard.record(
+                difficulty,
+                Entry {
+                    clicks: clicks as u32,
+                    date,
+                    three_bv: three_bv as u32,
+                    time: Duration::from_millis(millis),
+                    undos: undos as u32,
+                },
+            );
+        }
+        leaderboard
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(STORAGE_KEY)
+            .map(|value| Leaderboard::deserialize(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        storage.save(STORAGE_KEY, &self.serialize())
+    }
+}
+
+/// seconds since the unix epoch. `SystemTime` panics in the browser so it asks javascript there
+pub fn unix_time() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    return (js_sys::Date::now() / 1000.0) as u64;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGINNER: Difficulty = Difficulty {
+        columns: 9,
+        mines: 10,
+        rows: 9,
+    };
+
+    fn entry(seconds: u64, undos: u32) -> Entry {
+        Entry {
+            clicks: 20,
+            date: 1_600_000_000 + seconds,
+            three_bv: 15,
+            time: Duration::from_secs(seconds),
+            undos,
+        }
+    }
+
+    #[test]
+    fn fewer_undos_come_before_faster_times() {
+        let mut leaderboard = Leaderboard::default();
+        assert_eq!(leaderboard.record(BEGINNER, entry(30, 0)), Some(0));
+        assert_eq!(leaderboard.record(BEGINNER, entry(10, 1)), Some(1));
+        assert_eq!(leaderboard.record(BEGINNER, entry(20, 0)), Some(0));
+        assert_eq!(leaderboard.record(BEGINNER, entry(5, 1)), Some(2));
+        // ties keep the entry that got there first ahead
+        assert_eq!(leaderboard.record(BEGINNER, entry(20, 0)), Some(1));
+
+        let best: Vec<_> = leaderboard
+            .best(BEGINNER)
+            .iter()
+            .map(|entry| (entry.time.as_secs(), entry.undos))
+            .collect();
+        assert_eq!(best, vec![(20, 0), (20, 0), (30, 0), (5, 1), (10, 1)]);
+    }
+
+    #[test]
+    fn only_the_best_ten_are_kept() {
+        let mut leaderboard = Leaderboard::default();
+        for seconds in 1..=LEADERBOARD_SIZE as u64 {
+            assert!(leaderboard
+                .record(BEGINNER, entry(seconds * 10, 0))
+                .is_some());
+        }
+        assert_eq!(leaderboard.record(BEGINNER, entry(1_000, 0)), None);
+        assert_eq!(leaderboard.record(BEGINNER, entry(10, 1)), None);
+        assert_eq!(leaderboard.record(BEGINNER, entry(15, 0)), Some(1));
+
+        let best = leaderboard.best(BEGINNER);
+        assert_eq!(best.len(), LEADERBOARD_SIZE);
+        assert_eq!(best.last().unwrap().time, Duration::from_secs(90));
+        // other difficulties have boards of their own
+        assert!(leaderboard
+            .best(Difficulty {
+                columns: 16,
+                mines: 40,
+                rows: 16
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.record(BEGINNER, entry(30, 0));
+        leaderboard.record(BEGINNER, entry(10, 2));
+        let expert = Difficulty {
+            columns: 30,
+            mines: 99,
+            rows: 16,
+        };
+        leaderboard.record(expert, entry(120, 0));
+
+        let loaded = Leaderboard::deserialize(&leaderboard.serialize());
+        assert_eq!(loaded.best(BEGINNER), leaderboard.best(BEGINNER));
+        assert_eq!(loaded.best(expert), leaderboard.best(expert));
+    }
+
+    #[test]
+    fn unknown_versions_and_broken_lines_are_ignored() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.record(BEGINNER, entry(30, 0));
+        let serialized = leaderboard.serialize();
+
+        let unknown = serialized.replacen(VERSION, "v0", 1);
+        assert!(Leaderboard::deserialize(&unknown).best(BEGINNER).is_empty());
+
+        let broken = format!("{}9 9 10 fast 0 0 0 0\n9 9 10 1000\n", serialized);
+        let loaded = Leaderboard::deserialize(&broken);
+        assert_eq!(loaded.best(BEGINNER), leaderboard.best(BEGINNER));
+    }
+}
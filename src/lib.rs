@@ -1,18 +1,66 @@
 extern crate web_sys;
+mod animation;
+mod camera;
 mod components;
+mod config;
+pub mod core;
+mod hint;
+mod hud;
+mod keyboard;
+pub mod leaderboard;
 mod log;
+mod menu;
 mod mouse;
+mod overlay;
+mod playback;
+pub mod replay;
+pub mod save;
+pub mod statistics;
+pub mod storage;
+mod touch;
 
+use crate::core::{layout, Clicks, Game, GameStats, Generation};
+use animation::AnimationPlugin;
 use bevy::prelude::*;
-use components::ComponentsPlugin;
+use camera::{CameraPlugin, MainCamera};
+use components::{
+    Board, ComponentsPlugin, GameChangedEvent, LoadGameEvent, NewGameEvent, PlayReplayEvent,
+};
+pub use config::GameConfig;
+use hint::HintPlugin;
+use hud::HudPlugin;
+use keyboard::KeyboardPlugin;
+use leaderboard::Leaderboard;
+use menu::MenuPlugin;
 use mouse::MousePlugin;
+use overlay::OverlayPlugin;
+use playback::PlaybackPlugin;
+use replay::Replay;
+use save::SavedGame;
+use statistics::Statistics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use storage::PlatformStorage;
+use touch::TouchPlugin;
 use wasm_bindgen::prelude::*;
 
+static NEW_GAME_REQUESTED: AtomicBool = AtomicBool::new(false);
+static REPLAY_REQUESTED: Mutex<Option<Replay>> = Mutex::new(None);
+static LAYOUT_REQUESTED: Mutex<Option<Game>> = Mutex::new(None);
+/// a copy of the game on the board, kept for exporting its layout from outside of the app
+static CURRENT_GAME: Mutex<Option<Game>> = Mutex::new(None);
+static CURRENT_STATS: Mutex<Option<GameStats>> = Mutex::new(None);
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 enum AppState {
     MainMenu,
     InGame,
     Paused,
+    /// the best times, opened from the main menu
+    Leaderboard,
+    /// the lifetime statistics, opened from the main menu
+    Statistics,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -33,70 +81,237 @@ fn load_images(asset_server: Res<AssetServer>) {
     asset_server.load_untyped("mine.png");
     asset_server.load_untyped("u_lose.png");
     asset_server.load_untyped("u_win.png");
+    asset_server.load_untyped(menu::FONT);
 }
 fn setup(mut commands: Commands) {
     // cameras
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(MainCamera);
     commands.spawn_bundle(UiCameraBundle::default());
-    // Add walls
-    let wall_thickness = 10.0;
-    let bounds = Vec2::new(400.0, 400.0);
-
-    // left
-    commands.spawn_bundle(SpriteBundle {
-        transform: Transform::from_xyz(-bounds.x / 2.0, 0.0, 0.0),
-        sprite: Sprite {
-            color: Color::PINK,
-            custom_size: Some(Vec2::new(wall_thickness, bounds.y + wall_thickness)),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-    // right
-    commands.spawn_bundle(SpriteBundle {
-        transform: Transform::from_xyz(bounds.x / 2.0, 0.0, 0.0),
-        sprite: Sprite {
-            color: Color::PINK,
-            custom_size: Some(Vec2::new(wall_thickness, bounds.y + wall_thickness)),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-
-    // bottom
-    commands.spawn_bundle(SpriteBundle {
-        transform: Transform::from_xyz(0.0, -bounds.y / 2.0, 0.0),
-        sprite: Sprite {
-            color: Color::PINK,
-            custom_size: Some(Vec2::new(bounds.x + wall_thickness, wall_thickness)),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-    // top
-    commands.spawn_bundle(SpriteBundle {
-        transform: Transform::from_xyz(0.0, bounds.y / 2.0, 0.0),
-        sprite: Sprite {
-            color: Color::PINK,
-            custom_size: Some(Vec2::new(bounds.x + wall_thickness, wall_thickness)),
-            ..Default::default()
-        },
-        ..Default::default()
-    });
+}
+
+/// starts a new game from outside of the app, e.g. a button on the page embedding it
+#[wasm_bindgen]
+pub fn new_game() {
+    NEW_GAME_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+fn forward_new_game_request(mut ev_new_game: EventWriter<NewGameEvent>) {
+    if NEW_GAME_REQUESTED.swap(false, Ordering::Relaxed) {
+        ev_new_game.send(NewGameEvent);
+    }
+}
+
+/// the replay of the last finished game, to be shared or played back later with `play_replay`
+#[wasm_bindgen]
+pub fn export_replay() -> Option<String> {
+    Replay::load(&PlatformStorage::default()).map(|replay| replay.serialize())
+}
+
+/// plays back a replay exported with `export_replay`, leaving any menu that is open
+#[wasm_bindgen]
+pub fn play_replay(replay: &str) -> Result<(), JsValue> {
+    let replay = Replay::deserialize(replay).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    *REPLAY_REQUESTED.lock().unwrap() = Some(replay);
+    Ok(())
+}
+
+fn forward_replay_request(
+    mut ev_play_replay: EventWriter<PlayReplayEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let replay = if let Some(replay) = REPLAY_REQUESTED.lock().unwrap().take() {
+        replay
+    } else {
+        return;
+    };
+    ev_play_replay.send(PlayReplayEvent(replay));
+    leave_menus(&mut state);
+}
+
+/// the game on the board, as long as it is over. the layout of a game that is still being played
+/// would give away where its mines are
+fn finished_game() -> Result<Game, JsValue> {
+    match CURRENT_GAME.lock().unwrap().as_ref() {
+        Some(game) if game.is_over() => Ok(game.clone()),
+        Some(_) => Err(JsValue::from_str(
+            "finish the game before exporting its layout",
+        )),
+        None => Err(JsValue::from_str("there is no board yet")),
+    }
+}
+
+/// the layout of the finished game on the board as a text grid of `*` for mines and `.` for safe
+/// cells, see `core::layout`
+#[wasm_bindgen]
+pub fn export_layout() -> Result<String, JsValue> {
+    Ok(layout::to_text(&finished_game()?))
+}
+
+/// the layout of the finished game on the board in the mbf format
+#[wasm_bindgen]
+pub fn export_layout_mbf() -> Result<Vec<u8>, JsValue> {
+    layout::to_mbf(&finished_game()?).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// plays a layout written as a text grid, like the ones from `export_layout`
+#[wasm_bindgen]
+pub fn import_layout(text: &str) -> Result<(), JsValue> {
+    let game = layout::from_text(text).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    *LAYOUT_REQUESTED.lock().unwrap() = Some(game);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn import_layout_mbf(bytes: &[u8]) -> Result<(), JsValue> {
+    let game = layout::from_mbf(bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    *LAYOUT_REQUESTED.lock().unwrap() = Some(game);
+    Ok(())
+}
+
+fn forward_layout_request(
+    mut ev_load_game: EventWriter<LoadGameEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let game = if let Some(game) = LAYOUT_REQUESTED.lock().unwrap().take() {
+        game
+    } else {
+        return;
+    };
+    ev_load_game.send(LoadGameEvent(SavedGame {
+        clicks: Clicks::default(),
+        elapsed: Duration::ZERO,
+        game,
+        imported: true,
+        ranked: false,
+        undos: 0,
+    }));
+    leave_menus(&mut state);
+}
+
+/// How the game on the board has been played, as of the last move.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub chord_clicks: u32,
+    pub clicks: u32,
+    pub efficiency: f64,
+    pub left_clicks: u32,
+    pub right_clicks: u32,
+    pub seconds: f64,
+    pub solved_three_bv: u32,
+    pub three_bv: u32,
+    pub three_bv_per_second: f64,
+}
+
+impl From<GameStats> for Stats {
+    fn from(stats: GameStats) -> Self {
+        Stats {
+            chord_clicks: stats.clicks.chord,
+            clicks: stats.clicks.total(),
+            efficiency: stats.efficiency(),
+            left_clicks: stats.clicks.left,
+            right_clicks: stats.clicks.right,
+            seconds: stats.elapsed.as_secs_f64(),
+            solved_three_bv: stats.solved_three_bv,
+            three_bv: stats.three_bv,
+            three_bv_per_second: stats.three_bv_per_second(),
+        }
+    }
+}
+
+/// the 3BV, clicks and efficiency of the game on the board, which are final once it is over
+#[wasm_bindgen]
+pub fn game_stats() -> Option<Stats> {
+    CURRENT_STATS.lock().unwrap().map(Stats::from)
+}
+
+fn publish_current_game(
+    board_query: Query<&Board>,
+    new_board_query: Query<&Board, Added<Board>>,
+    mut ev_changed: EventReader<GameChangedEvent>,
+) {
+    if ev_changed.iter().count() == 0 && new_board_query.iter().next().is_none() {
+        return;
+    }
+    if let Some(board) = board_query.iter().next() {
+        *CURRENT_GAME.lock().unwrap() = Some(board.game.clone());
+        *CURRENT_STATS.lock().unwrap() = Some(board.stats());
+    }
+}
+
+/// requests from the page play straight away, even if a menu is open
+fn leave_menus(state: &mut State<AppState>) {
+    if state.current() == &AppState::InGame {
+        return;
+    }
+    if let Err(err) = state.replace(AppState::InGame) {
+        warn!("could not leave the menus: {:?}", err);
+    }
 }
 
 #[wasm_bindgen]
 pub fn run() {
+    start(GameConfig::default());
+}
+
+/// starts a game on a board with `rows` rows, `columns` columns and `mines` mines. passing a
+/// `seed` makes the board reproducible and `no_guess` only deals boards that can be solved
+/// without guessing. boards the main menu wouldn't allow are refused
+#[wasm_bindgen]
+pub fn run_with_config(
+    rows: usize,
+    columns: usize,
+    mines: u16,
+    seed: Option<u64>,
+    no_guess: bool,
+) -> Result<(), JsValue> {
+    let generation = if no_guess {
+        Generation::no_guess()
+    } else {
+        Generation::Random
+    };
+    let config = GameConfig::new(rows, columns, mines)
+        .map_err(|err| JsValue::from_str(&err.to_string()))?
+        .with_seed(seed)
+        .with_generation(generation);
+    start(config);
+    Ok(())
+}
+
+pub fn start(config: GameConfig) {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
+    let storage = PlatformStorage::default();
+    let leaderboard = Leaderboard::load(&storage);
+    let statistics = Statistics::load(&storage);
+
     App::new()
+        .insert_resource(config)
+        .insert_resource(storage)
+        .insert_resource(leaderboard)
+        .insert_resource(statistics)
         .add_plugins(DefaultPlugins)
         .add_plugin(MousePlugin)
+        .add_plugin(KeyboardPlugin)
+        .add_plugin(TouchPlugin)
         .add_plugin(ComponentsPlugin)
-        .add_state(AppState::InGame)
+        .add_plugin(CameraPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(HintPlugin)
+        .add_plugin(OverlayPlugin)
+        .add_plugin(PlaybackPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_state(AppState::MainMenu)
         .insert_resource(ClearColor(Color::rgb(0.9, 0.9, 0.9)))
         .add_startup_system(load_images)
         .add_startup_system(setup)
+        .add_system(forward_new_game_request)
+        .add_system(forward_replay_request)
+        .add_system(forward_layout_request)
+        .add_system(publish_current_game)
         .run();
 }
@@ -0,0 +1,155 @@
+use super::{
+    button_color, clicked_button, column, difficulty_name, overlay, spawn_button, text,
+    ScrollingColumn, FONT,
+};
+use crate::leaderboard::{Difficulty, Leaderboard};
+use crate::{AppState, GameConfig};
+
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct LeaderboardScreen;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+struct LeaderboardButton;
+
+/// the presets, along with the board picked in the main menu when it is a custom one
+fn shown_difficulties(config: &GameConfig) -> Vec<Difficulty> {
+    let mut difficulties: Vec<Difficulty> = [
+        GameConfig::BEGINNER,
+        GameConfig::INTERMEDIATE,
+        GameConfig::EXPERT,
+    ]
+    .iter()
+    .map(|preset| Difficulty {
+        columns: preset.columns,
+        mines: preset.mines,
+        rows: preset.rows,
+    })
+    .collect();
+    let picked = Difficulty {
+        columns: config.columns,
+        mines: config.mines,
+        rows: config.rows,
+    };
+    if !difficulties.contains(&picked) {
+        difficulties.push(picked);
+    }
+    difficulties
+}
+
+fn spawn_leaderboard_screen(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    leaderboard: Res<Leaderboard>,
+) {
+    let font: Handle<Font> = asset_server.load(FONT);
+    commands
+        .spawn_bundle(overlay())
+        .insert(LeaderboardScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(column())
+                .insert(ScrollingColumn::default())
+                .with_children(|parent| {
+                    parent.spawn_bundle(text(
+                        "Leaderboard",
+                        font.clone(),
+                        48.0,
+                        Color::MIDNIGHT_BLUE,
+                    ));
+                    for difficulty in shown_difficulties(&config) {
+                        parent.spawn_bundle(text(
+                            &difficulty_name(&difficulty),
+                            font.clone(),
+                            24.0,
+                            Color::MIDNIGHT_BLUE,
+                        ));
+                        let entries = leaderboard.best(difficulty);
+                        if entries.is_empty() {
+                            parent.spawn_bundle(text(
+                                "No wins yet",
+                                font.clone(),
+                                16.0,
+                                Color::MIDNIGHT_BLUE,
+                            ));
+                        }
+                        for (place, entry) in entries.iter().enumerate() {
+                            let undos = match entry.undos {
+                                0 => String::new(),
+                                1 => ", 1 undo".to_string(),
+                                undos => format!(", {} undos", undos),
+                            };
+                            parent.spawn_bundle(text(
+                                &format!(
+                                    "{}. {:.2}s  3BV/s {:.2}  {:.0}% efficiency{}",
+                                    place + 1,
+                                    entry.time.as_secs_f64(),
+                                    entry.three_bv_per_second(),
+                                    entry.efficiency() * 100.0,
+                                    undos
+                                ),
+                                font.clone(),
+                                16.0,
+                                Color::MIDNIGHT_BLUE,
+                            ));
+                        }
+                    }
+                    spawn_button(parent, font.clone(), "Back", 160.0, LeaderboardButton);
+                });
+        });
+}
+
+fn despawn_leaderboard_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<LeaderboardScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn leaderboard_buttons(
+    buttons: Query<(&Interaction, &LeaderboardButton)>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        if let Err(err) = state.set(AppState::MainMenu) {
+            warn!("could not go back to the main menu: {:?}", err);
+        }
+        return;
+    }
+    if clicked_button(&mut mouse_button_input, &buttons).is_some() {
+        if let Err(err) = state.set(AppState::MainMenu) {
+            warn!("could not go back to the main menu: {:?}", err);
+        }
+    }
+}
+
+fn leaderboard_colors(mut buttons: Query<(&Interaction, &mut UiColor), With<LeaderboardButton>>) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = button_color(*interaction, false);
+    }
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Leaderboard).with_system(spawn_leaderboard_screen),
+        );
+        app.add_system_set(
+            SystemSet::on_update(AppState::Leaderboard)
+                .with_system(leaderboard_buttons)
+                .with_system(leaderboard_colors),
+        );
+        app.add_system_set(
+            SystemSet::on_exit(AppState::Leaderboard).with_system(despawn_leaderboard_screen),
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use super::{
+    button_color, clicked_button, column, overlay, row, spawn_button, text, ScrollingColumn, FONT,
+};
+use crate::animation::Animations;
+use crate::components::{LoadGameEvent, NewGameEvent};
+use crate::config::{MAX_COLUMNS, MAX_ROWS, MIN_SIZE};
+use crate::core::Generation;
+use crate::mouse::ControlScheme;
+use crate::save::SavedGame;
+use crate::storage::PlatformStorage;
+use crate::{AppState, GameConfig};
+
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Rows,
+    Columns,
+    Mines,
+}
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Rows => "Rows",
+            Setting::Columns => "Columns",
+            Setting::Mines => "Mines",
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuButton {
+    Preset(GameConfig),
+    Adjust(Setting, i32),
+    /// whether cells open in waves and the end of a game is played out
+    Animations,
+    /// right clicking an opened cell flags around it
+    AutoFlag,
+    /// left clicking an opened cell chords it
+    ClickToChord,
+    /// picks up the game that was left unfinished
+    Continue,
+    /// moves on to the next control scheme preset
+    Controls,
+    Leaderboard,
+    /// only deals boards that can be cleared without guessing
+    NoGuess,
+    /// whether right clicks go through a question mark
+    QuestionMarks,
+    /// turns undo off so wins are played straight through
+    Ranked,
+    Start,
+    Statistics,
+}
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+/// the name of the control scheme in use
+#[derive(Component)]
+struct ControlsText;
+
+fn same_size(a: &GameConfig, b: &GameConfig) -> bool {
+    a.rows == b.rows && a.columns == b.columns && a.mines == b.mines
+}
+
+fn setting_value(config: &GameConfig, setting: Setting) -> usize {
+    match setting {
+        Setting::Rows => config.rows,
+        Setting::Columns => config.columns,
+        Setting::Mines => config.mines as usize,
+    }
+}
+
+fn spawn_main_menu(
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    control_scheme: Res<ControlScheme>,
+    mut commands: Commands,
+    storage: Res<PlatformStorage>,
+) {
+    let font: Handle<Font> = asset_server.load(FONT);
+    let saved = SavedGame::load(&*storage).is_some();
+
+    commands
+        .spawn_bundle(overlay())
+        .insert(MainMenu)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(column())
+                .insert(ScrollingColumn::default())
+                .with_children(|parent| {
+                    parent.spawn_bundle(text(
+                        "wasmsweeper",
+                        font.clone(),
+                        48.0,
+                        Color::MIDNIGHT_BLUE,
+                    ));
+                    if saved {
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            "Continue",
+                            220.0,
+                            MenuButton::Continue,
+                        );
+                    }
+                    for (label, preset) in [
+                        ("Beginner", GameConfig::BEGINNER),
+                        ("Intermediate", GameConfig::INTERMEDIATE),
+                        ("Expert", GameConfig::EXPERT),
+                    ] {
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            label,
+                            220.0,
+                            MenuButton::Preset(preset),
+                        );
+                    }
+
+                    for setting in [Setting::Rows, Setting::Columns, Setting::Mines] {
+                        parent.spawn_bundle(row()).with_children(|parent| {
+                            spawn_button(
+                                parent,
+                                font.clone(),
+                                "-",
+                                40.0,
+                                MenuButton::Adjust(setting, -1),
+                            );
+                            parent
+                                .spawn_bundle(text(
+                                    &format!(
+                                        "{}: {}",
+                                        setting.label(),
+                                        setting_value(&config, setting)
+                                    ),
+                                    font.clone(),
+                                    24.0,
+                                    Color::MIDNIGHT_BLUE,
+                                ))
+                                .insert(SettingText(setting));
+                            spawn_button(
+                                parent,
+                                font.clone(),
+                                "+",
+                                40.0,
+                                MenuButton::Adjust(setting, 1),
+                            );
+                        });
+                    }
+
+                    spawn_button(parent, font.clone(), "No guess", 220.0, MenuButton::NoGuess);
+                    spawn_button(
+                        parent,
+                        font.clone(),
+                        "Question marks",
+                        220.0,
+                        MenuButton::QuestionMarks,
+                    );
+                    parent.spawn_bundle(row()).with_children(|parent| {
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            "Controls",
+                            140.0,
+                            MenuButton::Controls,
+                        );
+                        parent
+                            .spawn_bundle(text(
+                                control_scheme.name(),
+                                font.clone(),
+                                24.0,
+                                Color::MIDNIGHT_BLUE,
+                            ))
+                            .insert(ControlsText);
+                    });
+                    parent.spawn_bundle(row()).with_children(|parent| {
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            "Auto flag",
+                            160.0,
+                            MenuButton::AutoFlag,
+                        );
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            "Click to chord",
+                            160.0,
+                            MenuButton::ClickToChord,
+                        );
+                    });
+                    spawn_button(
+                        parent,
+                        font.clone(),
+                        "Animations",
+                        220.0,
+                        MenuButton::Animations,
+                    );
+                    spawn_button(parent, font.clone(), "Ranked", 220.0, MenuButton::Ranked);
+                    spawn_button(parent, font.clone(), "Start", 220.0, MenuButton::Start);
+                    spawn_button(
+                        parent,
+                        font.clone(),
+                        "Statistics",
+                        220.0,
+                        MenuButton::Statistics,
+                    );
+                    spawn_button(
+                        parent,
+                        font.clone(),
+                        "Leaderboard",
+                        220.0,
+                        MenuButton::Leaderboard,
+                    );
+                });
+        });
+}
+
+fn despawn_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn main_menu_buttons(
+    mut animations: ResMut<Animations>,
+    buttons: Query<(&Interaction, &MenuButton)>,
+    mut config: ResMut<GameConfig>,
+    mut control_scheme: ResMut<ControlScheme>,
+    mut ev_load_game: EventWriter<LoadGameEvent>,
+    mut ev_new_game: EventWriter<NewGameEvent>,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    mut state: ResMut<State<AppState>>,
+    storage: Res<PlatformStorage>,
+) {
+    let button = if let Some(button) = clicked_button(&mut mouse_button_input, &buttons) {
+        button
+    } else {
+        return;
+    };
+
+    match button {
+        MenuButton::Preset(preset) => {
+            config.rows = preset.rows;
+            config.columns = preset.columns;
+            config.mines = preset.mines;
+        }
+        MenuButton::Adjust(setting, step) => {
+            let value = setting_value(&config, setting) as i32 + step;
+            match setting {
+                Setting::Rows => {
+                    config.rows = value.clamp(MIN_SIZE as i32, MAX_ROWS as i32) as usize
+                }
+                Setting::Columns => {
+                    config.columns = value.clamp(MIN_SIZE as i32, MAX_COLUMNS as i32) as usize
+                }
+                Setting::Mines => config.mines = value.clamp(1, config.max_mines() as i32) as u16,
+            }
+            // shrinking the board can leave too many mines for it
+            let limit = config.max_mines() as u16;
+            config.mines = config.mines.min(limit);
+        }
+        MenuButton::Animations => animations.enabled = !animations.enabled,
+        MenuButton::AutoFlag => control_scheme.auto_flag = !control_scheme.auto_flag,
+        MenuButton::ClickToChord => control_scheme.click_to_chord = !control_scheme.click_to_chord,
+        MenuButton::Continue => {
+            let saved = if let Some(saved) = SavedGame::load(&*storage) {
+                saved
+            } else {
+                warn!("the saved game could not be loaded");
+                return;
+            };
+            // new games after this one keep the size of the saved board
+            config.rows = saved.game.height;
+            config.columns = saved.game.width;
+            config.mines = saved.game.mines;
+            ev_load_game.send(LoadGameEvent(saved));
+            if let Err(err) = state.set(AppState::InGame) {
+                warn!("could not start the game: {:?}", err);
+            }
+        }
+        MenuButton::Controls => *control_scheme = control_scheme.next_preset(),
+        MenuButton::Leaderboard => {
+            if let Err(err) = state.set(AppState::Leaderboard) {
+                warn!("could not open the leaderboard: {:?}", err);
+            }
+        }
+        MenuButton::NoGuess => {
+            config.generation = match config.generation {
+                Generation::Random => Generation::no_guess(),
+                Generation::NoGuess { .. } => Generation::Random,
+            }
+        }
+        MenuButton::QuestionMarks => config.question_marks = !config.question_marks,
+        MenuButton::Ranked => config.ranked = !config.ranked,
+        MenuButton::Start => {
+            ev_new_game.send(NewGameEvent);
+            if let Err(err) = state.set(AppState::InGame) {
+                warn!("could not start the game: {:?}", err);
+            }
+        }
+        MenuButton::Statistics => {
+            if let Err(err) = state.set(AppState::Statistics) {
+                warn!("could not open the statistics: {:?}", err);
+            }
+        }
+    }
+}
+
+fn main_menu_colors(
+    animations: Res<Animations>,
+    config: Res<GameConfig>,
+    control_scheme: Res<ControlScheme>,
+    mut buttons: Query<(&Interaction, &MenuButton, &mut UiColor)>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        let selected = match button {
+            MenuButton::Preset(preset) => same_size(preset, &config),
+            MenuButton::Animations => animations.enabled,
+            MenuButton::AutoFlag => control_scheme.auto_flag,
+            MenuButton::ClickToChord => control_scheme.click_to_chord,
+            MenuButton::NoGuess => matches!(config.generation, Generation::NoGuess { .. }),
+            MenuButton::QuestionMarks => config.question_marks,
+            MenuButton::Ranked => config.ranked,
+            _ => false,
+        };
+        *color = button_color(*interaction, selected);
+    }
+}
+
+fn update_setting_text(config: Res<GameConfig>, mut text_query: Query<(&SettingText, &mut Text)>) {
+    if !config.is_changed() {
+        return;
+    }
+    for (SettingText(setting), mut text) in text_query.iter_mut() {
+        text.sections[0].value =
+            format!("{}: {}", setting.label(), setting_value(&config, *setting));
+    }
+}
+
+fn update_controls_text(
+    control_scheme: Res<ControlScheme>,
+    mut text_query: Query<&mut Text, With<ControlsText>>,
+) {
+    if !control_scheme.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = control_scheme.name().to_string();
+    }
+}
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(spawn_main_menu));
+        app.add_system_set(
+            SystemSet::on_update(AppState::MainMenu)
+                .with_system(main_menu_buttons)
+                .with_system(main_menu_colors)
+                .with_system(update_setting_text)
+                .with_system(update_controls_text),
+        );
+        app.add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(despawn_main_menu));
+    }
+}
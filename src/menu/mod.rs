@@ -0,0 +1,205 @@
+use crate::leaderboard::Difficulty;
+use crate::GameConfig;
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+mod leaderboard;
+mod main_menu;
+mod pause;
+mod statistics;
+use leaderboard::LeaderboardPlugin;
+use main_menu::MainMenuPlugin;
+use pause::PausePlugin;
+use statistics::StatisticsPlugin;
+
+pub const FONT: &str = "DejaVuSans-Bold.ttf";
+pub const TEXT_COLOR: Color = Color::WHITE;
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const BUTTON_COLOR: Color = Color::MIDNIGHT_BLUE;
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.6);
+const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.8);
+const SELECTED_BUTTON_COLOR: Color = Color::PINK;
+/// how far one notch of the mouse wheel scrolls a menu
+const SCROLL_LINE: f32 = 40.0;
+
+/// A column of menu items that scrolls with the mouse wheel when it is taller than the window.
+#[derive(Component, Default)]
+pub struct ScrollingColumn {
+    offset: f32,
+}
+
+pub fn button_color(interaction: Interaction, selected: bool) -> UiColor {
+    match interaction {
+        Interaction::Clicked => PRESSED_BUTTON_COLOR,
+        Interaction::Hovered => HOVERED_BUTTON_COLOR,
+        Interaction::None if selected => SELECTED_BUTTON_COLOR,
+        Interaction::None => BUTTON_COLOR,
+    }
+    .into()
+}
+
+/// the name of the preset with the difficulty, or its size for custom boards
+pub fn difficulty_name(difficulty: &Difficulty) -> String {
+    for (name, preset) in [
+        ("Beginner", GameConfig::BEGINNER),
+        ("Intermediate", GameConfig::INTERMEDIATE),
+        ("Expert", GameConfig::EXPERT),
+    ] {
+        if difficulty.rows == preset.rows
+            && difficulty.columns == preset.columns
+            && difficulty.mines == preset.mines
+        {
+            return name.to_string();
+        }
+    }
+    format!(
+        "{}x{}, {} mines",
+        difficulty.rows, difficulty.columns, difficulty.mines
+    )
+}
+
+/// a full window node that covers the board, laying its children out from top to bottom
+pub fn overlay() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            overflow: Overflow::Hidden,
+            ..Default::default()
+        },
+        color: BACKGROUND_COLOR.into(),
+        ..Default::default()
+    }
+}
+
+/// lays its children out from top to bottom without drawing anything itself. put inside an
+/// `overlay` with a `ScrollingColumn` so that long menus can still be reached on short windows
+pub fn column() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    }
+}
+
+/// lays its children out from left to right without drawing anything itself
+pub fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    }
+}
+
+pub fn text(value: &str, font: Handle<Font>, font_size: f32, color: Color) -> TextBundle {
+    TextBundle {
+        style: Style {
+            margin: Rect::all(Val::Px(5.0)),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font,
+                font_size,
+                color,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn spawn_button<T: Component>(
+    parent: &mut ChildBuilder,
+    font: Handle<Font>,
+    label: &str,
+    width: f32,
+    button: T,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(40.0)),
+                margin: Rect::all(Val::Px(5.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: BUTTON_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(text(label, font, 24.0, TEXT_COLOR));
+        });
+}
+
+/// the button that was just clicked. buttons act when the mouse is released over them, and the
+/// release is consumed so it can't also reach a cell once the state changes
+pub fn clicked_button<T: Component + Copy>(
+    mouse_button_input: &mut Input<MouseButton>,
+    buttons: &Query<(&Interaction, &T)>,
+) -> Option<T> {
+    if !mouse_button_input.just_released(MouseButton::Left) {
+        return None;
+    }
+    let (_, button) = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)?;
+    mouse_button_input.reset(MouseButton::Left);
+    Some(*button)
+}
+
+/// the column is centred in the window, so it can be scrolled by up to half of what doesn't fit
+/// either way. growing the window scrolls it back into view
+fn scroll_columns(
+    mut column_query: Query<(&mut ScrollingColumn, &mut Style, &Node, &Parent)>,
+    mut ev_wheel: EventReader<MouseWheel>,
+    node_query: Query<&Node>,
+) {
+    let scrolled: f32 = ev_wheel
+        .iter()
+        .map(|ev| match ev.unit {
+            MouseScrollUnit::Line => ev.y * SCROLL_LINE,
+            MouseScrollUnit::Pixel => ev.y,
+        })
+        .sum();
+    for (mut column, mut style, node, parent) in column_query.iter_mut() {
+        let window = if let Ok(window) = node_query.get(parent.0) {
+            window
+        } else {
+            continue;
+        };
+        let overflow = ((node.size.y - window.size.y) / 2.0).max(0.0);
+        let offset = (column.offset - scrolled).clamp(-overflow, overflow);
+        if offset != column.offset {
+            column.offset = offset;
+            style.position.top = Val::Px(offset);
+        }
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(LeaderboardPlugin);
+        app.add_plugin(MainMenuPlugin);
+        app.add_plugin(PausePlugin);
+        app.add_plugin(StatisticsPlugin);
+        app.add_system(scroll_columns);
+    }
+}
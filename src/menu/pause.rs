@@ -0,0 +1,88 @@
+use super::{button_color, clicked_button, overlay, spawn_button, text, FONT};
+use crate::AppState;
+
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseButton {
+    Resume,
+    MainMenu,
+}
+
+fn spawn_pause_menu(asset_server: Res<AssetServer>, mut commands: Commands) {
+    let font: Handle<Font> = asset_server.load(FONT);
+
+    commands
+        .spawn_bundle(overlay())
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("Paused", font.clone(), 48.0, Color::MIDNIGHT_BLUE));
+            spawn_button(parent, font.clone(), "Resume", 220.0, PauseButton::Resume);
+            spawn_button(
+                parent,
+                font.clone(),
+                "Main menu",
+                220.0,
+                PauseButton::MainMenu,
+            );
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, menu_query: Query<Entity, With<PauseMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_menu_buttons(
+    buttons: Query<(&Interaction, &PauseButton)>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        // the game would otherwise see the same press and pause again
+        keyboard_input.reset(KeyCode::Escape);
+        if let Err(err) = state.pop() {
+            warn!("could not resume the game: {:?}", err);
+        }
+        return;
+    }
+
+    match clicked_button(&mut mouse_button_input, &buttons) {
+        Some(PauseButton::Resume) => {
+            if let Err(err) = state.pop() {
+                warn!("could not resume the game: {:?}", err);
+            }
+        }
+        Some(PauseButton::MainMenu) => {
+            if let Err(err) = state.replace(AppState::MainMenu) {
+                warn!("could not go back to the main menu: {:?}", err);
+            }
+        }
+        None => {}
+    }
+}
+
+fn pause_menu_colors(mut buttons: Query<(&Interaction, &mut UiColor), With<PauseButton>>) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = button_color(*interaction, false);
+    }
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Paused).with_system(spawn_pause_menu));
+        app.add_system_set(
+            SystemSet::on_update(AppState::Paused)
+                .with_system(pause_menu_buttons)
+                .with_system(pause_menu_colors),
+        );
+        app.add_system_set(SystemSet::on_exit(AppState::Paused).with_system(despawn_pause_menu));
+    }
+}
@@ -0,0 +1,216 @@
+use super::{
+    button_color, clicked_button, column, difficulty_name, overlay, row, spawn_button, text,
+    ScrollingColumn, FONT,
+};
+use crate::statistics::{Record, Statistics};
+use crate::storage::PlatformStorage;
+use crate::AppState;
+
+use bevy::prelude::*;
+
+const HISTOGRAM_BUCKETS: usize = 10;
+const BAR_WIDTH: f32 = 20.0;
+const BAR_HEIGHT: f32 = 60.0;
+
+#[derive(Component)]
+struct StatisticsScreen;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum StatisticsButton {
+    /// forgets every game played so far
+    Reset,
+    Back,
+}
+
+/// one bar per range of win times, lined up along the bottom, with the range they cover under them
+fn spawn_histogram(parent: &mut ChildBuilder, font: Handle<Font>, record: &Record) {
+    let histogram = record.histogram(HISTOGRAM_BUCKETS);
+    let (first, last) = match (histogram.first(), histogram.last()) {
+        (Some((first, _, _)), Some((_, last, _))) => (*first, *last),
+        _ => return,
+    };
+    let most = histogram
+        .iter()
+        .map(|(_, _, wins)| *wins)
+        .max()
+        .unwrap_or(1);
+
+    let mut bars = row();
+    // the y axis points up, so the start of the cross axis is the bottom
+    bars.style.align_items = AlignItems::FlexStart;
+    parent.spawn_bundle(bars).with_children(|parent| {
+        for (_, _, wins) in histogram.iter() {
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(
+                        Val::Px(BAR_WIDTH),
+                        Val::Px(2.0 + BAR_HEIGHT * *wins as f32 / most as f32),
+                    ),
+                    margin: Rect::all(Val::Px(1.0)),
+                    ..Default::default()
+                },
+                color: Color::MIDNIGHT_BLUE.into(),
+                ..Default::default()
+            });
+        }
+    });
+    parent.spawn_bundle(text(
+        &format!("{}s to {}s", first, last),
+        font,
+        14.0,
+        Color::MIDNIGHT_BLUE,
+    ));
+}
+
+fn spawn_statistics(commands: &mut Commands, font: Handle<Font>, statistics: &Statistics) {
+    commands
+        .spawn_bundle(overlay())
+        .insert(StatisticsScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(column())
+                .insert(ScrollingColumn::default())
+                .with_children(|parent| {
+                    parent.spawn_bundle(text(
+                        "Statistics",
+                        font.clone(),
+                        48.0,
+                        Color::MIDNIGHT_BLUE,
+                    ));
+                    if statistics.records.is_empty() {
+                        parent.spawn_bundle(text(
+                            "No games finished yet",
+                            font.clone(),
+                            18.0,
+                            Color::MIDNIGHT_BLUE,
+                        ));
+                    }
+
+                    for (difficulty, record) in statistics.records.iter() {
+                        parent.spawn_bundle(text(
+                            &difficulty_name(difficulty),
+                            font.clone(),
+                            24.0,
+                            Color::MIDNIGHT_BLUE,
+                        ));
+                        parent.spawn_bundle(text(
+                            &format!(
+                                "{} played, {} won ({:.0}%), {} lost",
+                                record.played(),
+                                record.wins,
+                                record.win_percentage(),
+                                record.losses
+                            ),
+                            font.clone(),
+                            18.0,
+                            Color::MIDNIGHT_BLUE,
+                        ));
+                        let average = match record.average_time() {
+                            Some(average) => format!("{:.1}s", average.as_secs_f64()),
+                            None => "-".to_string(),
+                        };
+                        parent.spawn_bundle(text(
+                            &format!(
+                                "streak {}, longest {}, average time {}",
+                                record.current_streak, record.longest_streak, average
+                            ),
+                            font.clone(),
+                            18.0,
+                            Color::MIDNIGHT_BLUE,
+                        ));
+                        spawn_histogram(parent, font.clone(), record);
+                    }
+
+                    parent.spawn_bundle(row()).with_children(|parent| {
+                        spawn_button(
+                            parent,
+                            font.clone(),
+                            "Reset",
+                            160.0,
+                            StatisticsButton::Reset,
+                        );
+                        spawn_button(parent, font.clone(), "Back", 160.0, StatisticsButton::Back);
+                    });
+                });
+        });
+}
+
+fn spawn_statistics_screen(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    statistics: Res<Statistics>,
+) {
+    spawn_statistics(&mut commands, asset_server.load(FONT), &statistics);
+}
+
+fn despawn_statistics_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<StatisticsScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn statistics_buttons(
+    asset_server: Res<AssetServer>,
+    buttons: Query<(&Interaction, &StatisticsButton)>,
+    mut commands: Commands,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_button_input: ResMut<Input<MouseButton>>,
+    screen_query: Query<Entity, With<StatisticsScreen>>,
+    mut state: ResMut<State<AppState>>,
+    mut statistics: ResMut<Statistics>,
+    mut storage: ResMut<PlatformStorage>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        if let Err(err) = state.set(AppState::MainMenu) {
+            warn!("could not go back to the main menu: {:?}", err);
+        }
+        return;
+    }
+
+    match clicked_button(&mut mouse_button_input, &buttons) {
+        Some(StatisticsButton::Reset) => {
+            if let Err(err) = statistics.reset(&mut *storage) {
+                warn!("could not reset the statistics: {}", err);
+            }
+            for entity in screen_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            spawn_statistics(&mut commands, asset_server.load(FONT), &statistics);
+        }
+        Some(StatisticsButton::Back) => {
+            if let Err(err) = state.set(AppState::MainMenu) {
+                warn!("could not go back to the main menu: {:?}", err);
+            }
+        }
+        None => {}
+    }
+}
+
+fn statistics_colors(mut buttons: Query<(&Interaction, &mut UiColor), With<StatisticsButton>>) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = button_color(*interaction, false);
+    }
+}
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Statistics).with_system(spawn_statistics_screen),
+        );
+        app.add_system_set(
+            SystemSet::on_update(AppState::Statistics)
+                .with_system(statistics_buttons)
+                .with_system(statistics_colors),
+        );
+        app.add_system_set(
+            SystemSet::on_exit(AppState::Statistics).with_system(despawn_statistics_screen),
+        );
+    }
+}
@@ -0,0 +1,73 @@
+/// What each mouse button does on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlScheme {
+    /// right clicking an opened cell flags the neighbours that have to be mines
+    pub auto_flag: bool,
+    /// pressing both buttons together chords the cell under the cursor
+    pub both_buttons: bool,
+    /// left clicking an opened cell chords it
+    pub click_to_chord: bool,
+    /// right clicking a covered cell flags it
+    pub flags: bool,
+    /// middle clicking an opened cell chords it
+    pub middle_click: bool,
+}
+
+impl ControlScheme {
+    /// the way the original game played, with chording on both buttons or the middle one
+    pub const CLASSIC: ControlScheme = ControlScheme {
+        auto_flag: false,
+        both_buttons: true,
+        click_to_chord: false,
+        flags: true,
+        middle_click: true,
+    };
+    /// every shortcut turned on
+    pub const MODERN: ControlScheme = ControlScheme {
+        auto_flag: true,
+        both_buttons: true,
+        click_to_chord: true,
+        flags: true,
+        middle_click: true,
+    };
+    /// only left clicks, for playing without ever placing a flag. chording needs flags, so it is
+    /// off too
+    pub const NO_FLAG: ControlScheme = ControlScheme {
+        auto_flag: false,
+        both_buttons: false,
+        click_to_chord: false,
+        flags: false,
+        middle_click: false,
+    };
+
+    pub const PRESETS: [(&'static str, ControlScheme); 3] = [
+        ("Classic", ControlScheme::CLASSIC),
+        ("Modern", ControlScheme::MODERN),
+        ("No flag", ControlScheme::NO_FLAG),
+    ];
+
+    /// the name of the preset these controls match, if any
+    pub fn name(&self) -> &'static str {
+        ControlScheme::PRESETS
+            .iter()
+            .find(|(_, preset)| preset == self)
+            .map_or("Custom", |(name, _)| name)
+    }
+
+    /// the preset after the one these controls match, starting over from the first after the last
+    /// one or a custom scheme
+    pub fn next_preset(&self) -> ControlScheme {
+        let presets = ControlScheme::PRESETS;
+        let index = presets
+            .iter()
+            .position(|(_, preset)| preset == self)
+            .map_or(0, |index| (index + 1) % presets.len());
+        presets[index].1
+    }
+}
+
+impl Default for ControlScheme {
+    fn default() -> Self {
+        ControlScheme::MODERN
+    }
+}
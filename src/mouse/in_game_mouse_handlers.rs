@@ -1,134 +1,311 @@
+use super::ControlScheme;
+use crate::camera::{window_to_world, MainCamera};
 use crate::components::{
-    AllCellsOpenedEvent, ApplyMaterialEvent, BasicCell, Board, ChordSolvedCellEvent,
-    ClearOpenCellsEvent, FlagSolvedCellEvent, MineClickedEvent, CELL_COLOR,
+    result_contains, ActionEvent, BasicCell, Board, NewGameEvent, ResultSprite, CELL_SIZE,
 };
+use crate::playback::Playback;
+use crate::replay::Action;
 use crate::AppState;
 
 use bevy::prelude::*;
+use bevy::render::camera::Camera;
 
-pub fn left_click(
+/// shown over every cell the held buttons would open, the way the cells of the original game
+/// looked pushed in
+const PRESSED_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+
+/// Pressing both buttons together chords the cell under the cursor when either of them is let go,
+/// if the control scheme allows it. the other button's release is swallowed, so it doesn't open or
+/// flag anything on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BothButtons {
+    Up,
+    Held,
+    /// one of the buttons has been let go and the chord is done, but the other is still down
+    Released,
+}
+
+impl Default for BothButtons {
+    fn default() -> Self {
+        BothButtons::Up
+    }
+}
+
+/// covers one of the cells that would be opened if the held buttons were let go
+#[derive(Component)]
+pub struct PressedMarker;
+
+/// the cursor in the same coordinates as the sprites
+fn cursor_position(
+    windows: &Windows,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let (camera, camera_transform) = camera_query.iter().next()?;
+    Some(window_to_world(window, camera, camera_transform, cursor))
+}
+
+/// the cell under the cursor
+fn hovered_cell<'a>(
+    cell_query: &'a Query<&BasicCell>,
+    windows: &Windows,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<&'a BasicCell> {
+    let cursor = cursor_position(windows, camera_query)?;
+    cell_query
+        .iter()
+        .find(|basic_cell| basic_cell.contains(cursor))
+}
+
+pub fn track_both_buttons(
+    mut both_buttons: ResMut<BothButtons>,
+    control_scheme: Res<ControlScheme>,
     mouse_button_input: Res<Input<MouseButton>>,
-    mut board_query: Query<&mut Board>,
-    mut cell_query: Query<&BasicCell>,
-    mut ev_all_opened: EventWriter<AllCellsOpenedEvent>,
-    mut ev_apply_material: EventWriter<ApplyMaterialEvent>,
-    mut ev_chord_cell: EventWriter<ChordSolvedCellEvent>,
-    mut ev_mine_clicked: EventWriter<MineClickedEvent>,
-    mut ev_open_cells: EventWriter<ClearOpenCellsEvent>,
-    mut windows: ResMut<Windows>,
 ) {
-    if !mouse_button_input.just_released(MouseButton::Left) {
-        return;
+    let left = mouse_button_input.pressed(MouseButton::Left);
+    let right = mouse_button_input.pressed(MouseButton::Right);
+    let released = mouse_button_input.just_released(MouseButton::Left)
+        || mouse_button_input.just_released(MouseButton::Right);
+    if left && right && control_scheme.both_buttons && *both_buttons == BothButtons::Up {
+        *both_buttons = BothButtons::Held;
+    } else if !left && !right && !released {
+        *both_buttons = BothButtons::Up;
     }
-    let mut board = board_query.single_mut();
+}
 
-    if board.game_over {
+/// chords on letting go of both buttons or the middle one
+#[allow(clippy::too_many_arguments)]
+pub fn chord_click(
+    mut both_buttons: ResMut<BothButtons>,
+    control_scheme: Res<ControlScheme>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut ev_action: EventWriter<ActionEvent>,
+    playback: Res<Playback>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let released = mouse_button_input.just_released(MouseButton::Left)
+        || mouse_button_input.just_released(MouseButton::Right);
+    let both = *both_buttons == BothButtons::Held && released;
+    let middle =
+        control_scheme.middle_click && mouse_button_input.just_released(MouseButton::Middle);
+    if both {
+        *both_buttons = BothButtons::Released;
+    } else if !middle {
         return;
     }
 
-    let window = windows.get_primary_mut().unwrap();
-    let cursor = if let Some(cursor) = window.cursor_position() {
-        cursor - Vec2::new(window.width(), window.height()) / 2.0
-    } else {
+    let board = board_query.single();
+    if board.game.is_over() || playback.is_active() {
         return;
-    };
-    for basic_cell in cell_query.iter_mut() {
-        if !basic_cell.contains(cursor) {
-            continue;
-        }
-        let row = basic_cell.row;
-        let column = basic_cell.column;
-        if !board.initialized {
-            board.fill_board(40, (row, column)).unwrap();
+    }
+    // letting go away from the board doesn't chord anything
+    if let Some(basic_cell) = hovered_cell(&cell_query, &windows, &camera_query) {
+        let position = (basic_cell.row, basic_cell.column);
+        if board.game.cells[position.0][position.1].opened {
+            ev_action.send(ActionEvent(Action::Chord(position)));
         }
+    }
+}
 
-        let cell = &mut board.cells[row][column];
+pub fn spawn_pressed_markers(mut commands: Commands) {
+    // a cell and its neighbours are the most that can be pressed at once
+    for _ in 0..9 {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: PRESSED_COLOR,
+                    custom_size: Some(Vec2::new(CELL_SIZE, CELL_SIZE)),
+                    ..Default::default()
+                },
+                visibility: Visibility { is_visible: false },
+                ..Default::default()
+            })
+            .insert(PressedMarker);
+    }
+}
 
-        if cell.flagged {
-            return;
+/// pushes in the cells that letting go of the held buttons would open: the covered cell under
+/// the left button, or the covered neighbours of an opened one that it would chord. both buttons
+/// or the middle one push in the cell and all of its covered neighbours. the cells follow the
+/// cursor and nothing is pushed in away from the board
+#[allow(clippy::too_many_arguments)]
+pub fn show_pressed_cells(
+    both_buttons: Res<BothButtons>,
+    control_scheme: Res<ControlScheme>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut marker_query: Query<(&mut Transform, &mut Visibility), With<PressedMarker>>,
+    playback: Res<Playback>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let board = board_query.single();
+    let mut pressed = vec![];
+    let hovered = if board.game.is_over() || playback.is_active() {
+        None
+    } else {
+        hovered_cell(&cell_query, &windows, &camera_query)
+    };
+    if let Some(basic_cell) = hovered {
+        let position = (basic_cell.row, basic_cell.column);
+        let opened = board.game.cells[position.0][position.1].opened;
+        let middle = control_scheme.middle_click && mouse_button_input.pressed(MouseButton::Middle);
+        if *both_buttons == BothButtons::Held || middle {
+            pressed.push(position);
+            pressed.extend(board.game.neighbours(position));
+        } else if *both_buttons == BothButtons::Up && mouse_button_input.pressed(MouseButton::Left)
+        {
+            if !opened {
+                pressed.push(position);
+            } else if control_scheme.click_to_chord {
+                pressed.extend(board.game.neighbours(position));
+            }
         }
+    }
+    pressed.retain(|(row, col)| {
+        let cell = &board.game.cells[*row][*col];
+        !cell.opened && !cell.flagged()
+    });
 
-        if cell.opened {
-            ev_chord_cell.send(ChordSolvedCellEvent((row, column)));
-            return;
+    let mut positions = cell_query
+        .iter()
+        .filter(|basic_cell| pressed.contains(&(basic_cell.row, basic_cell.column)))
+        .map(|basic_cell| basic_cell.position);
+    for (mut transform, mut visibility) in marker_query.iter_mut() {
+        match positions.next() {
+            Some(position) => {
+                transform.translation = position.truncate().extend(2.0);
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
         }
+    }
+}
 
-        cell.opened = true;
-        ev_apply_material.send(ApplyMaterialEvent((row, column)));
+/// the buttons may still be down when the board is left behind
+pub fn hide_pressed_cells(mut marker_query: Query<&mut Visibility, With<PressedMarker>>) {
+    for mut visibility in marker_query.iter_mut() {
+        visibility.is_visible = false;
+    }
+}
 
-        if cell.mine {
-            ev_mine_clicked.send(MineClickedEvent);
-            return;
-        }
+#[allow(clippy::too_many_arguments)]
+pub fn left_click(
+    both_buttons: Res<BothButtons>,
+    control_scheme: Res<ControlScheme>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut ev_action: EventWriter<ActionEvent>,
+    playback: Res<Playback>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if !mouse_button_input.just_released(MouseButton::Left)
+        || *both_buttons != BothButtons::Up
+        || playback.is_active()
+    {
+        return;
+    }
+    let board = board_query.single();
 
-        if cell.value == 0 {
-            ev_open_cells.send(ClearOpenCellsEvent((row, column)));
+    if board.game.is_over() {
+        return;
+    }
+
+    let cursor = if let Some(cursor) = cursor_position(&windows, &camera_query) {
+        cursor
+    } else {
+        return;
+    };
+    for basic_cell in cell_query.iter() {
+        if !basic_cell.contains(cursor) {
+            continue;
         }
+        let row = basic_cell.row;
+        let column = basic_cell.column;
 
-        board.cells_unopened -= 1;
-        if board.cells_unopened == 0 {
-            ev_all_opened.send(AllCellsOpenedEvent);
+        if !board.game.cells[row][column].opened {
+            ev_action.send(ActionEvent(Action::Reveal((row, column))));
+        } else if control_scheme.click_to_chord {
+            ev_action.send(ActionEvent(Action::Chord((row, column))));
         }
+        return;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn right_click(
-    asset_server: Res<AssetServer>,
+    both_buttons: Res<BothButtons>,
+    control_scheme: Res<ControlScheme>,
     mouse_button_input: Res<Input<MouseButton>>,
-    mut board_query: Query<&mut Board>,
-    mut cell_query: Query<(&BasicCell, Entity, &mut Sprite)>,
-    mut commands: Commands,
-    mut ev_flag_cell: EventWriter<FlagSolvedCellEvent>,
-    mut windows: ResMut<Windows>,
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut ev_action: EventWriter<ActionEvent>,
+    playback: Res<Playback>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
 ) {
-    if !mouse_button_input.just_released(MouseButton::Right) {
+    if !mouse_button_input.just_released(MouseButton::Right)
+        || *both_buttons != BothButtons::Up
+        || playback.is_active()
+    {
         return;
     }
 
-    let mut board = board_query.single_mut();
-    if board.game_over {
+    let board = board_query.single();
+    if board.game.is_over() {
         return;
     }
 
-    let window = windows.get_primary_mut().unwrap();
-    let cursor = if let Some(cursor) = window.cursor_position() {
-        cursor - Vec2::new(window.width(), window.height()) / 2.0
+    let cursor = if let Some(cursor) = cursor_position(&windows, &camera_query) {
+        cursor
     } else {
         return;
     };
-    for (basic_cell, entity, mut sprite) in cell_query.iter_mut() {
+    for basic_cell in cell_query.iter() {
         if !basic_cell.contains(cursor) {
             continue;
         }
         let row = basic_cell.row;
         let column = basic_cell.column;
-        let cell = &mut board.cells[row][column];
-        if cell.opened {
-            ev_flag_cell.send(FlagSolvedCellEvent((row, column)));
-            return;
-        }
 
-        cell.flagged = !cell.flagged;
+        if board.game.cells[row][column].opened {
+            if control_scheme.auto_flag {
+                ev_action.send(ActionEvent(Action::AutoFlag((row, column))));
+            }
+        } else if control_scheme.flags {
+            ev_action.send(ActionEvent(Action::ToggleFlag((row, column))));
+        }
+        return;
+    }
+}
 
-        if cell.flagged {
-            let child = commands
-                .spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(22.0, 22.0)),
-                        ..Default::default()
-                    },
+/// clicking the win or lose sprite starts a new game
+pub fn click_result(
+    images: Res<Assets<Image>>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    result_query: Query<(&Transform, &Handle<Image>), With<ResultSprite>>,
+    mut ev_new_game: EventWriter<NewGameEvent>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    if !mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
 
-                    texture: asset_server.load("flag.png"),
-                    ..Default::default()
-                })
-                .id();
-
-            commands.entity(entity).push_children(&[child]);
-            sprite.color = Color::WHITE;
-        } else {
-            sprite.color = CELL_COLOR;
-            commands.entity(entity).despawn_descendants();
-        }
+    let cursor = if let Some(cursor) = cursor_position(&windows, &camera_query) {
+        cursor
+    } else {
+        return;
+    };
+    if result_contains(&images, &result_query, cursor) {
+        ev_new_game.send(NewGameEvent);
     }
 }
 
@@ -136,10 +313,25 @@ pub struct InGameMousePlugin;
 
 impl Plugin for InGameMousePlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<BothButtons>();
+        app.init_resource::<ControlScheme>();
+        app.add_startup_system(spawn_pressed_markers);
         app.add_system_set(
             SystemSet::on_update(AppState::InGame)
-                .with_system(left_click.label("left_click"))
-                .with_system(right_click.label("right_click")),
+                .with_system(track_both_buttons.label("both_buttons"))
+                .with_system(
+                    chord_click
+                        .label("chord_click")
+                        .after("both_buttons")
+                        .before("left_click")
+                        .before("right_click"),
+                )
+                .with_system(left_click.label("left_click").after("both_buttons"))
+                .with_system(right_click.label("right_click").after("both_buttons"))
+                .with_system(show_pressed_cells.after("chord_click"))
+                .with_system(click_result),
         );
+        app.add_system_set(SystemSet::on_pause(AppState::InGame).with_system(hide_pressed_cells));
+        app.add_system_set(SystemSet::on_exit(AppState::InGame).with_system(hide_pressed_cells));
     }
 }
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
+mod control_scheme;
 mod in_game_mouse_handlers;
+pub use control_scheme::ControlScheme;
 use in_game_mouse_handlers::InGameMousePlugin;
 
 pub struct MousePlugin;
@@ -0,0 +1,156 @@
+use crate::camera::{window_to_world, MainCamera};
+use crate::components::{BasicCell, Board, GameChangedEvent, CELL_COLOR, CELL_SIZE};
+use crate::core::solver;
+use crate::menu::FONT;
+use crate::AppState;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+/// turns the mine probability overlay on or off
+pub struct ToggleOverlayEvent;
+
+/// Tints every covered cell by its chance of being a mine, for practising guesses.
+#[derive(Default)]
+pub struct ProbabilityOverlay {
+    pub enabled: bool,
+    /// the chance of each cell being a mine, `None` when the board is too open ended to work out
+    chances: Option<Vec<Vec<Option<f64>>>>,
+}
+
+/// the chance of the cell under the cursor, shown just above it
+#[derive(Component)]
+pub struct ProbabilityLabel;
+
+/// from green for safe to red for certain mines
+fn heat(chance: f64) -> Color {
+    let chance = chance as f32;
+    Color::rgb(0.2 + 0.75 * chance, 0.8 - 0.6 * chance, 0.25)
+}
+
+pub fn spawn_probability_label(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(FONT),
+                    font_size: 14.0,
+                    color: Color::MIDNIGHT_BLUE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            ..Default::default()
+        })
+        .insert(ProbabilityLabel);
+}
+
+/// works the chances out again whenever the board changes, and recolours the covered cells. ranked
+/// games are played without it
+pub fn update_overlay(
+    board_query: Query<&Board>,
+    new_board_query: Query<&Board, Added<Board>>,
+    mut cell_query: Query<(&BasicCell, &mut Sprite)>,
+    mut ev_changed: EventReader<GameChangedEvent>,
+    mut ev_toggle: EventReader<ToggleOverlayEvent>,
+    mut overlay: ResMut<ProbabilityOverlay>,
+) {
+    let board = board_query.single();
+    let toggles = ev_toggle.iter().count();
+    let changed = ev_changed.iter().count() > 0 || new_board_query.iter().next().is_some();
+    if toggles % 2 == 1 {
+        overlay.enabled = !overlay.enabled && !board.ranked;
+        if board.ranked {
+            info!("the probability overlay is off in ranked games");
+        }
+    } else if !changed {
+        return;
+    }
+
+    overlay.chances = if overlay.enabled && !board.ranked && !board.game.is_over() {
+        solver::probabilities(&board.game)
+    } else {
+        None
+    };
+    if overlay.enabled && overlay.chances.is_none() && !board.game.is_over() {
+        info!("too many ways to lay out the mines to work out their chances");
+    }
+
+    for (basic_cell, mut sprite) in cell_query.iter_mut() {
+        let cell = &board.game.cells[basic_cell.row][basic_cell.column];
+        // flags show their own texture, opened cells their numbers and mines are shown once the
+        // game is lost
+        if cell.opened || cell.flagged() || (cell.mine && board.game.is_over()) {
+            continue;
+        }
+        let chance = overlay
+            .chances
+            .as_ref()
+            .and_then(|chances| chances[basic_cell.row][basic_cell.column]);
+        sprite.color = chance.map(heat).unwrap_or(CELL_COLOR);
+    }
+}
+
+/// the covered cell under the cursor and its chance of being a mine
+fn hovered_chance<'a>(
+    board: &Board,
+    cell_query: &'a Query<&BasicCell>,
+    chances: &[Vec<Option<f64>>],
+    windows: &Windows,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<(&'a BasicCell, f64)> {
+    let window = windows.get_primary()?;
+    let (camera, camera_transform) = camera_query.iter().next()?;
+    let cursor = window_to_world(window, camera, camera_transform, window.cursor_position()?);
+    let basic_cell = cell_query
+        .iter()
+        .find(|basic_cell| basic_cell.contains(cursor))?;
+    if board.game.cells[basic_cell.row][basic_cell.column].flagged() {
+        return None;
+    }
+    Some((basic_cell, chances[basic_cell.row][basic_cell.column]?))
+}
+
+pub fn hover_probability(
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut label_query: Query<(&mut Text, &mut Transform), With<ProbabilityLabel>>,
+    overlay: Res<ProbabilityOverlay>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let board = board_query.single();
+    let (mut text, mut transform) = label_query.single_mut();
+
+    let hovered = overlay
+        .chances
+        .as_ref()
+        .and_then(|chances| hovered_chance(board, &cell_query, chances, &windows, &camera_query));
+
+    text.sections[0].value = match hovered {
+        Some((basic_cell, chance)) => {
+            transform.translation =
+                (basic_cell.position.truncate() + Vec2::new(0.0, CELL_SIZE)).extend(5.0);
+            format!("{:.0}%", chance * 100.0)
+        }
+        None => String::new(),
+    };
+}
+
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ToggleOverlayEvent>();
+        app.init_resource::<ProbabilityOverlay>();
+        app.add_startup_system(spawn_probability_label);
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(update_overlay.label("overlay").after("apply_game_changes"))
+                .with_system(hover_probability.after("overlay")),
+        );
+    }
+}
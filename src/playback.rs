@@ -0,0 +1,204 @@
+use crate::components::{
+    ActionAppliedEvent, ActionEvent, AllCellsOpenedEvent, Board, LoadGameEvent, MineClickedEvent,
+    NewGameEvent, PlayReplayEvent,
+};
+use crate::replay::Replay;
+use crate::storage::PlatformStorage;
+use crate::AppState;
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.0;
+
+/// the game being played, as a replay. games continued from a save aren't recorded since the
+/// moves made before saving are gone
+#[derive(Default)]
+pub struct Recorder {
+    replay: Option<Replay>,
+}
+
+/// a replay being played back on the board. the player's own moves are ignored while one is
+/// loaded, until a new game is started
+pub struct Playback {
+    /// how far into the replay playback has got
+    clock: Duration,
+    next: usize,
+    pub paused: bool,
+    replay: Option<Replay>,
+    pub speed: f32,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            clock: Duration::ZERO,
+            next: 0,
+            paused: false,
+            replay: None,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Playback {
+    pub fn is_active(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.replay
+            .as_ref()
+            .map(|replay| self.next >= replay.steps.len())
+            .unwrap_or(false)
+    }
+}
+
+/// records the actions the board went through with, in the order it made them
+#[allow(clippy::too_many_arguments)]
+pub fn record_actions(
+    board_query: Query<&Board>,
+    new_board_query: Query<&Board, Added<Board>>,
+    mut ev_applied: EventReader<ActionAppliedEvent>,
+    mut ev_all_opened: EventReader<AllCellsOpenedEvent>,
+    mut ev_mine_clicked: EventReader<MineClickedEvent>,
+    playback: Res<Playback>,
+    mut recorder: ResMut<Recorder>,
+    mut storage: ResMut<PlatformStorage>,
+) {
+    if let Some(board) = new_board_query.iter().next() {
+        recorder.replay = if board.game.initialized || playback.is_active() {
+            None
+        } else {
+            Some(Replay::new(&board.game, board.question_marks, board.ranked))
+        };
+    }
+
+    let actions: Vec<_> = ev_applied
+        .iter()
+        .map(|ActionAppliedEvent(action)| *action)
+        .collect();
+    let game_over = ev_mine_clicked.iter().count() + ev_all_opened.iter().count() > 0;
+
+    let replay = if let Some(replay) = recorder.replay.as_mut() {
+        replay
+    } else {
+        return;
+    };
+    let board = board_query.single();
+    for action in actions {
+        replay.record(action, board.elapsed);
+    }
+
+    if game_over {
+        match replay.save(&mut *storage) {
+            Ok(()) => info!("saved the replay of the game, press R to watch it"),
+            Err(err) => warn!("could not save the replay: {}", err),
+        }
+    }
+}
+
+/// space pauses, full stop steps through one action at a time while paused, plus and minus change
+/// the speed and R watches the replay of the last finished game
+pub fn playback_controls(
+    mut ev_play_replay: EventWriter<PlayReplayEvent>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    storage: Res<PlatformStorage>,
+) {
+    if keyboard_input.just_pressed(KeyCode::R) {
+        match Replay::load(&*storage) {
+            Some(replay) => ev_play_replay.send(PlayReplayEvent(replay)),
+            None => info!("there is no replay to watch yet"),
+        }
+    }
+    if !playback.is_active() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::Equals)
+        || keyboard_input.just_pressed(KeyCode::NumpadAdd)
+    {
+        playback.speed = (playback.speed * 2.0).min(MAX_SPEED);
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus)
+        || keyboard_input.just_pressed(KeyCode::NumpadSubtract)
+    {
+        playback.speed = (playback.speed / 2.0).max(MIN_SPEED);
+    }
+}
+
+/// feeds the replay's actions back, in the order they were recorded, through the same event the
+/// player's input sends
+pub fn play_back(
+    mut ev_action: EventWriter<ActionEvent>,
+    mut ev_load_game: EventReader<LoadGameEvent>,
+    mut ev_new_game: EventReader<NewGameEvent>,
+    mut ev_play_replay: EventReader<PlayReplayEvent>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    time: Res<Time>,
+) {
+    if let Some(PlayReplayEvent(replay)) = ev_play_replay.iter().last() {
+        // the board for the replay is only spawned at the end of this frame
+        *playback = Playback {
+            replay: Some(replay.clone()),
+            speed: playback.speed,
+            ..Default::default()
+        };
+        return;
+    }
+    if ev_new_game.iter().count() + ev_load_game.iter().count() > 0 {
+        playback.replay = None;
+        return;
+    }
+
+    let playback = &mut *playback;
+    let replay = if let Some(replay) = playback.replay.as_ref() {
+        replay
+    } else {
+        return;
+    };
+
+    let last = if playback.paused {
+        if !keyboard_input.just_pressed(KeyCode::Period) {
+            return;
+        }
+        match replay.steps.get(playback.next) {
+            Some(step) => playback.clock = step.time,
+            None => return,
+        }
+        playback.next + 1
+    } else {
+        playback.clock += time.delta().mul_f32(playback.speed);
+        replay.steps.len()
+    };
+
+    while playback.next < last {
+        let step = &replay.steps[playback.next];
+        if step.time > playback.clock {
+            break;
+        }
+        ev_action.send(ActionEvent(step.action));
+        playback.next += 1;
+    }
+}
+
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playback>();
+        app.init_resource::<Recorder>();
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(playback_controls.before("replay"))
+                .with_system(play_back.label("replay").before("game_rules"))
+                .with_system(record_actions.after("game_over")),
+        );
+    }
+}
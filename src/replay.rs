@@ -0,0 +1,366 @@
+use crate::config::GameConfig;
+use crate::core::{Change, Game, GameState, Generation, History};
+use crate::storage::Storage;
+
+use std::error::Error;
+use std::time::Duration;
+
+/// a few times what no guess games are laid out with, so a shared replay can't ask for far more
+const MAX_ATTEMPTS: u32 = 1_000;
+const STORAGE_KEY: &str = "replay";
+const VERSION: &str = "v1";
+
+/// Something the player did, as the board was asked to do it rather than what it changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// opens the cell, flooding outwards if it has no surrounding mines
+    Reveal((usize, usize)),
+    Chord((usize, usize)),
+    AutoFlag((usize, usize)),
+    ToggleFlag((usize, usize)),
+    /// takes back the last move
+    Undo,
+    /// makes the last undone move again
+    Redo,
+}
+
+impl Action {
+    /// makes the action on the game, adding moves to the history so they can be taken back.
+    /// `None` for an undo or redo that isn't allowed: a won game can't be walked back through,
+    /// and ranked games have to be played as they fall
+    pub fn apply(
+        self,
+        game: &mut Game,
+        history: &mut History,
+        question_marks: bool,
+        ranked: bool,
+    ) -> Result<Option<Vec<Change>>, Box<dyn Error>> {
+        let changes = match self {
+            Action::Undo | Action::Redo if ranked || game.state == GameState::Won => {
+                return Ok(None)
+            }
+            Action::Undo => return Ok(Some(history.undo(game))),
+            Action::Redo => return Ok(Some(history.redo(game))),
+            Action::Reveal(position) => game.reveal(position)?,
+            Action::Chord(position) => game.chord(position),
+            Action::AutoFlag(position) => game.auto_flag(position),
+            Action::ToggleFlag(position) => game.cycle_mark(position, question_marks),
+        };
+        history.record(&changes);
+        Ok(Some(changes))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Action::Reveal(_) => "reveal",
+            Action::Chord(_) => "chord",
+            Action::AutoFlag(_) => "autoflag",
+            Action::ToggleFlag(_) => "flag",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub action: Action,
+    /// time played when the action was made
+    pub time: Duration,
+}
+
+/// Everything needed to play a game again: the board it was dealt, which the seed and generation
+/// reproduce from the first click, and every action made on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub columns: usize,
+    pub generation: Generation,
+    pub mines: u16,
+    /// right clicks went through a question mark on the way from a flag back to blank
+    pub question_marks: bool,
+    /// undo and redo were turned off
+    pub ranked: bool,
+    pub rows: usize,
+    pub seed: u64,
+    /// only the actions the board went through with, so an undo that wasn't allowed isn't kept
+    pub steps: Vec<Step>,
+}
+
+impl Replay {
+    /// an empty replay for a game that hasn't been started
+    pub fn new(game: &Game, question_marks: bool, ranked: bool) -> Self {
+        Replay {
+            columns: game.width,
+            generation: game.generation,
+            mines: game.mines,
+            question_marks,
+            ranked,
+            rows: game.height,
+            seed: game.seed,
+            steps: vec![],
+        }
+    }
+
+    /// the game as it was before the first action
+    pub fn game(&self) -> Game {
+        Game::with_seed(self.rows, self.columns, self.mines, self.seed)
+            .with_generation(self.generation)
+    }
+
+    pub fn record(&mut self, action: Action, time: Duration) {
+        self.steps.push(Step { action, time });
+    }
+
+    /// a header line of `rows columns mines seed marks undos generation`, where the marks are
+    /// `flags` or `questions`, the undos are `undos` or `ranked` and the generation is `random` or
+    /// `noguess` followed by its attempts, then one line per step of `milliseconds action` with the
+    /// row and column for actions on a cell
+    pub fn serialize(&self) -> String {
+        let generation = match self.generation {
+            Generation::Random => "random".to_string(),
+            Generation::NoGuess { max_attempts } => format!("noguess {}", max_attempts),
+        };
+        let marks = if self.question_marks {
+            "questions"
+        } else {
+            "flags"
+        };
+        let undos = if self.ranked { "ranked" } else { "undos" };
+        let mut out = format!(
+            "{}\n{} {} {} {} {} {} {}\n",
+            VERSION, self.rows, self.columns, self.mines, self.seed, marks, undos, generation
+        );
+        for step in self.steps.iter() {
+            out.push_str(&format!("{} {}", step.time.as_millis(), step.action.name()));
+            match step.action {
+                Action::Reveal((row, column))
+                | Action::Chord((row, column))
+                | Action::AutoFlag((row, column))
+                | Action::ToggleFlag((row, column)) => {
+                    out.push_str(&format!(" {} {}", row, column))
+                }
+                Action::Undo | Action::Redo => {}
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn deserialize(value: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = value
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty());
+        if lines.next() != Some(VERSION) {
+            return Err("unknown replay version".into());
+        }
+
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or("missing header")?
+            .split_whitespace()
+            .collect();
+        let (rows, columns, mines, seed, marks, undos, generation) = match header[..] {
+            [rows, columns, mines, seed, marks, undos, "random"] => {
+                (rows, columns, mines, seed, marks, undos, Generation::Random)
+            }
+            [rows, columns, mines, seed, marks, undos, "noguess", attempts] => (
+                rows,
+                columns,
+                mines,
+                seed,
+                marks,
+                undos,
+                Generation::NoGuess {
+                    max_attempts: attempts.parse()?,
+                },
+            ),
+            _ => {
+                return Err("header is not `rows columns mines seed marks undos generation`".into())
+            }
+        };
+        let question_marks = match marks {
+            "questions" => true,
+            "flags" => false,
+            _ => return Err(format!("unknown marks `{}`", marks).into()),
+        };
+        let ranked = match undos {
+            "ranked" => true,
+            "undos" => false,
+            _ => return Err(format!("unknown undos `{}`", undos).into()),
+        };
+        let mut replay = Replay {
+            columns: columns.parse()?,
+            generation,
+            mines: mines.parse()?,
+            question_marks,
+            ranked,
+            rows: rows.parse()?,
+            seed: seed.parse()?,
+            steps: vec![],
+        };
+        // checked before the steps so a broken header can't ask for a huge board or generation
+        GameConfig::new(replay.rows, replay.columns, replay.mines)?;
+        if let Generation::NoGuess { max_attempts } = replay.generation {
+            if max_attempts > MAX_ATTEMPTS {
+                return Err(format!("no more than {} attempts are allowed", MAX_ATTEMPTS).into());
+            }
+        }
+
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (time, name, position) = match fields[..] {
+                [time, name] => (time, name, None),
+                [time, name, row, column] => {
+                    let position: (usize, usize) = (row.parse()?, column.parse()?);
+                    if position.0 >= replay.rows || position.1 >= replay.columns {
+                        return Err(format!("`{}` is outside of the board", line).into());
+                    }
+                    (time, name, Some(position))
+                }
+                _ => return Err(format!("can't read step `{}`", line).into()),
+            };
+            let action = match (name, position) {
+                ("reveal", Some(position)) => Action::Reveal(position),
+                ("chord", Some(position)) => Action::Chord(position),
+                ("autoflag", Some(position)) => Action::AutoFlag(position),
+                ("flag", Some(position)) => Action::ToggleFlag(position),
+                ("undo", None) => Action::Undo,
+                ("redo", None) => Action::Redo,
+                _ => return Err(format!("unknown action `{}`", line).into()),
+            };
+            replay.record(action, Duration::from_millis(time.parse()?));
+        }
+        Ok(replay)
+    }
+
+    /// the replay of the last game that was finished
+    pub fn load(storage: &dyn Storage) -> Option<Self> {
+        Replay::deserialize(&storage.load(STORAGE_KEY)?).ok()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        storage.save(STORAGE_KEY, &self.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Mark;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(&Game::with_seed(9, 9, 10, 42), true, false);
+        replay.record(Action::Reveal((4, 4)), Duration::ZERO);
+        replay.record(Action::ToggleFlag((0, 0)), Duration::from_millis(1_250));
+        replay.record(Action::Chord((4, 4)), Duration::from_millis(2_000));
+        replay.record(Action::AutoFlag((4, 4)), Duration::from_millis(2_500));
+        replay.record(Action::Undo, Duration::from_millis(3_000));
+        replay.record(Action::Redo, Duration::from_millis(3_001));
+        replay
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        assert_eq!(Replay::deserialize(&replay.serialize()).unwrap(), replay);
+
+        let mut no_guess = replay;
+        no_guess.generation = Generation::no_guess();
+        no_guess.question_marks = false;
+        no_guess.ranked = true;
+        assert_eq!(
+            Replay::deserialize(&no_guess.serialize()).unwrap(),
+            no_guess
+        );
+    }
+
+    fn cells(game: &Game) -> Vec<(bool, bool, Mark)> {
+        game.cells
+            .iter()
+            .flatten()
+            .map(|cell| (cell.mine, cell.opened, cell.mark))
+            .collect()
+    }
+
+    /// plays the actions the way the board does, recording the ones it went through with
+    fn play(replay: &mut Replay, actions: &[Action]) -> Game {
+        let mut game = replay.game();
+        let mut history = History::default();
+        for &action in actions {
+            let applied = action.apply(
+                &mut game,
+                &mut history,
+                replay.question_marks,
+                replay.ranked,
+            );
+            if applied.unwrap().is_some() {
+                replay.record(action, Duration::ZERO);
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn ranked_replays_end_on_the_same_board() {
+        let mut replay = Replay::new(&Game::with_seed(9, 9, 10, 42), false, true);
+        let mut opened = replay.game();
+        opened.reveal((4, 4)).unwrap();
+        let closed = opened
+            .cells
+            .iter()
+            .flatten()
+            .find(|cell| !cell.opened)
+            .map(|cell| (cell.row, cell.column))
+            .unwrap();
+        let actions = [
+            Action::Reveal((4, 4)),
+            Action::ToggleFlag(closed),
+            Action::Undo,
+            Action::Redo,
+        ];
+        let played = play(&mut replay, &actions);
+        assert_eq!(replay.steps.len(), 2);
+
+        let replay = Replay::deserialize(&replay.serialize()).unwrap();
+        assert!(replay.ranked);
+        let mut again = replay.clone();
+        again.steps.clear();
+        let actions: Vec<_> = replay.steps.iter().map(|step| step.action).collect();
+        assert_eq!(cells(&play(&mut again, &actions)), cells(&played));
+        assert_eq!(played.cells[closed.0][closed.1].mark, Mark::Flag);
+    }
+
+    #[test]
+    fn huge_replays_are_refused() {
+        for huge in [
+            "v1\n100000 100000 10 0 flags undos random\n",
+            "v1\n31 9 10 0 flags undos random\n",
+            "v1\n9 9 73 0 flags undos random\n",
+            "v1\n9 9 10 0 flags undos noguess 4294967295\n",
+        ] {
+            assert!(Replay::deserialize(huge).is_err(), "{}", huge);
+        }
+        assert!(Replay::deserialize("v1\n30 50 1491 0 flags undos noguess 1000\n").is_ok());
+    }
+
+    #[test]
+    fn broken_replays_are_refused() {
+        let serialized = replay().serialize();
+        for broken in [
+            serialized.replacen(VERSION, "v0", 1),
+            serialized.replacen("questions", "maybe", 1),
+            serialized.replacen(" undos ", " maybe ", 1),
+            serialized.replacen("reveal 4 4", "reveal 4 9", 1),
+            serialized.replacen("undo", "undo 1 1", 1),
+            serialized.replacen("9 9 10", "3 3 10", 1),
+            serialized.replacen("9 9 10", "100000 100000 10", 1),
+            serialized.replacen("9 9 10", "9 51 10", 1),
+            serialized.replacen("9 9 10", "9 9 0", 1),
+            serialized.replacen("random", "noguess 1000000000", 1),
+            format!("{}100 dance\n", serialized),
+        ] {
+            assert!(Replay::deserialize(&broken).is_err(), "{}", broken);
+        }
+    }
+}
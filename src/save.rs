@@ -0,0 +1,241 @@
+use crate::core::{Clicks, Game, GameState, Mark};
+use crate::storage::Storage;
+
+use std::error::Error;
+use std::time::Duration;
+
+const STORAGE_KEY: &str = "save";
+const VERSION: &str = "v1";
+
+const MINE: u8 = 1;
+const OPENED: u8 = 2;
+const FLAGGED: u8 = 4;
+const QUESTIONED: u8 = 8;
+
+/// A game that is still being played, kept so it can be picked up again after the page is closed.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub clicks: Clicks,
+    pub elapsed: Duration,
+    pub game: Game,
+    /// the board was built from a layout, so where the mines are was known from the start
+    pub imported: bool,
+    pub ranked: bool,
+    /// kept so that saving and continuing doesn't wipe the undo count
+    pub undos: u32,
+}
+
+impl SavedGame {
+    /// a header line of `rows columns mines seed milliseconds unopened undos ranked imported left
+    /// right chord` followed by one line per row, with a letter from `a` to `p` per cell holding whether
+    /// it is a mine, opened, flagged and question marked
+    pub fn serialize(&self) -> String {
+        let game = &self.game;
+        let mut out = format!(
+            "{}\n{} {} {} {} {} {} {} {} {} {} {} {}\n",
+            VERSION,
+            game.height,
+            game.width,
+            game.mines,
+            game.seed,
+            self.elapsed.as_millis(),
+            game.cells_unopened,
+            self.undos,
+            self.ranked as u8,
+            self.imported as u8,
+            self.clicks.left,
+            self.clicks.right,
+            self.clicks.chord
+        );
+        for row in game.cells.iter() {
+            for cell in row {
+                let bits = cell.mine as u8 * MINE
+                    + cell.opened as u8 * OPENED
+                    + cell.flagged() as u8 * FLAGGED
+                    + (cell.mark == Mark::Question) as u8 * QUESTIONED;
+                out.push((b'a' + bits) as char);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn deserialize(value: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = value.lines();
+        if lines.next() != Some(VERSION) {
+            return Err("unknown save version".into());
+        }
+
+        let mut fields = lines
+            .next()
+            .ok_or("missing header")?
+            .split_whitespace()
+            .map(|field| field.parse::<u64>());
+        let mut field = || -> Result<u64, Box<dyn Error>> {
+            Ok(fields
+                .next()
+                .ok_or("header has the wrong number of fields")??)
+        };
+        let rows = field()? as usize;
+        let columns = field()? as usize;
+        let mines = field()? as u16;
+        let seed = field()?;
+        let millis = field()?;
+        let unopened = field()?;
+        let undos = field()? as u32;
+        let ranked = field()? != 0;
+        let imported = field()? != 0;
+        let left = field()? as u32;
+        let right = field()? as u32;
+        let chord = field()? as u32;
+        if fields.next().is_some() {
+            return Err("header has the wrong number of fields".into());
+        }
+
+        // checked before building the game so a broken header can't ask for a huge board
+        let grid: Vec<&[u8]> = lines.map(|line| line.as_bytes()).collect();
+        if grid.len() != rows || grid.iter().any(|line| line.len() != columns) {
+            return Err("cells don't match the size of the board".into());
+        }
+
+        let mut game = Game::with_seed(rows, columns, mines, seed);
+        for (row, line) in grid.iter().enumerate() {
+            for (column, letter) in line.iter().enumerate() {
+                let bits = letter
+                    .checked_sub(b'a')
+                    .filter(|bits| *bits <= MINE + OPENED + FLAGGED + QUESTIONED)
+                    .ok_or("unknown cell")?;
+                let cell = &mut game.cells[row][column];
+                cell.mine = bits & MINE != 0;
+                cell.opened = bits & OPENED != 0;
+                cell.mark = match (bits & FLAGGED != 0, bits & QUESTIONED != 0) {
+                    (false, false) => Mark::Blank,
+                    (true, false) => Mark::Flag,
+                    (false, true) => Mark::Question,
+                    (true, true) => return Err("a cell can't have two marks".into()),
+                };
+            }
+        }
+        game.number_cells();
+        game.initialized = true;
+        game.state = GameState::Playing;
+
+        let cells = game.cells.iter().flatten();
+        if cells.clone().filter(|cell| cell.mine).count() != mines as usize {
+            return Err("wrong number of mines".into());
+        }
+        if cells.clone().any(|cell| cell.mine && cell.opened) {
+            return Err("a mine has already been opened".into());
+        }
+        game.cells_unopened = cells.filter(|cell| !cell.mine && !cell.opened).count();
+        if game.cells_unopened as u64 != unopened || game.cells_unopened == 0 {
+            return Err("wrong number of unopened cells".into());
+        }
+
+        Ok(SavedGame {
+            clicks: Clicks { chord, left, right },
+            elapsed: Duration::from_millis(millis),
+            game,
+            imported,
+            ranked,
+            undos,
+        })
+    }
+
+    /// a save that can't be read is treated as if there was none
+    pub fn load(storage: &dyn Storage) -> Option<Self> {
+        SavedGame::deserialize(&storage.load(STORAGE_KEY)?).ok()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        storage.save(STORAGE_KEY, &self.serialize())
+    }
+
+    /// once the game is over there is nothing left to continue
+    pub fn clear(storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        storage.remove(STORAGE_KEY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_game() -> SavedGame {
+        let mut game = Game::with_mines(4, 5, &[(0, 0), (0, 4), (3, 4)]);
+        game.reveal((3, 0)).unwrap();
+        game.cycle_mark((0, 0), true);
+        game.cycle_mark((0, 4), true);
+        game.cycle_mark((0, 4), true);
+        SavedGame {
+            clicks: Clicks {
+                chord: 1,
+                left: 2,
+                right: 3,
+            },
+            elapsed: Duration::from_millis(12_345),
+            game,
+            imported: true,
+            ranked: false,
+            undos: 4,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let saved = saved_game();
+        let loaded = SavedGame::deserialize(&saved.serialize()).unwrap();
+        assert_eq!(loaded.clicks, saved.clicks);
+        assert_eq!(loaded.elapsed, saved.elapsed);
+        assert_eq!(loaded.imported, saved.imported);
+        assert_eq!(loaded.ranked, saved.ranked);
+        assert_eq!(loaded.undos, saved.undos);
+        assert_eq!(loaded.game.seed, saved.game.seed);
+        assert_eq!(loaded.game.cells_unopened, saved.game.cells_unopened);
+        for (loaded, saved) in loaded
+            .game
+            .cells
+            .iter()
+            .flatten()
+            .zip(saved.game.cells.iter().flatten())
+        {
+            assert_eq!(
+                (loaded.mine, loaded.opened, loaded.mark, loaded.value),
+                (saved.mine, saved.opened, saved.mark, saved.value)
+            );
+        }
+        assert_eq!(loaded.game.cells[0][0].mark, Mark::Flag);
+        assert_eq!(loaded.game.cells[0][4].mark, Mark::Question);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let serialized = saved_game().serialize().replacen(VERSION, "v0", 1);
+        assert!(SavedGame::deserialize(&serialized).is_err());
+    }
+
+    #[test]
+    fn broken_saves_are_refused() {
+        let serialized = saved_game().serialize();
+        let mut lines: Vec<&str> = serialized.lines().collect();
+
+        let mut short_header = lines.clone();
+        let header = lines[1].rsplit_once(' ').unwrap().0;
+        short_header[1] = header;
+        assert!(SavedGame::deserialize(&short_header.join("\n")).is_err());
+
+        let long_header = format!("{} 0", lines[1]);
+        let mut with_long_header = lines.clone();
+        with_long_header[1] = &long_header;
+        assert!(SavedGame::deserialize(&with_long_header.join("\n")).is_err());
+
+        // a mine that has been opened means the game was already lost
+        let opened_mine = lines[2].replacen('f', "d", 1);
+        let mut with_opened_mine = lines.clone();
+        with_opened_mine[2] = &opened_mine;
+        assert!(SavedGame::deserialize(&with_opened_mine.join("\n")).is_err());
+
+        lines.pop();
+        assert!(SavedGame::deserialize(&lines.join("\n")).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::leaderboard::Difficulty;
+use crate::storage::Storage;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::time::Duration;
+
+const STORAGE_KEY: &str = "statistics";
+const VERSION: &str = "v1";
+
+/// Every finished game of one difficulty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    /// wins since the last loss
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub losses: u32,
+    /// how many wins took each whole number of seconds
+    pub times: BTreeMap<u64, u32>,
+    /// the time taken by every win added up
+    pub total_time: Duration,
+    pub wins: u32,
+}
+
+impl Record {
+    pub fn played(&self) -> u32 {
+        self.wins + self.losses
+    }
+
+    pub fn win_percentage(&self) -> f64 {
+        if self.played() == 0 {
+            return 0.0;
+        }
+        100.0 * self.wins as f64 / self.played() as f64
+    }
+
+    /// the average time of a win
+    pub fn average_time(&self) -> Option<Duration> {
+        if self.wins == 0 {
+            return None;
+        }
+        Some(self.total_time / self.wins)
+    }
+
+    /// the wins counted in up to `buckets` ranges of seconds of the same width, from the fastest
+    /// win to the slowest, as `(first second, last second, wins)`
+    pub fn histogram(&self, buckets: usize) -> Vec<(u64, u64, u32)> {
+        let (first, last) = match (self.times.keys().next(), self.times.keys().last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return vec![],
+        };
+        let width = (last - first) / buckets as u64 + 1;
+        // wins close together in time don't need every bucket
+        let used = (last - first) / width + 1;
+        let mut histogram: Vec<(u64, u64, u32)> = (0..used)
+            .map(|bucket| (first + bucket * width, first + (bucket + 1) * width - 1, 0))
+            .collect();
+        for (seconds, count) in self.times.iter() {
+            histogram[((seconds - first) / width) as usize].2 += count;
+        }
+        histogram
+    }
+
+    pub fn win(&mut self, time: Duration) {
+        self.wins += 1;
+        self.current_streak += 1;
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+        *self.times.entry(time.as_secs()).or_default() += 1;
+        self.total_time += time;
+    }
+
+    pub fn lose(&mut self) {
+        self.losses += 1;
+        self.current_streak = 0;
+    }
+}
+
+/// Lifetime stats for every difficulty that has been played to the end at least once.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    pub records: BTreeMap<Difficulty, Record>,
+}
+
+impl Statistics {
+    pub fn record(&mut self, difficulty: Difficulty) -> &mut Record {
+        self.records.entry(difficulty).or_default()
+    }
+
+    /// one line per difficulty of `rows columns mines wins losses current_streak longest_streak
+    /// total_milliseconds` followed by a `seconds:wins` pair for every time that has been won in
+    pub fn serialize(&self) -> String {
+        let mut out = format!("{}\n", VERSION);
+        for (difficulty, record) in self.records.iter() {
+            out.push_str(&format!(
+                "{} {} {} {} {} {} {} {}",
+                difficulty.rows,
+                difficulty.columns,
+                difficulty.mines,
+                record.wins,
+                record.losses,
+                record.current_streak,
+                record.longest_streak,
+                record.total_time.as_millis()
+            ));
+            for (seconds, count) in record.times.iter() {
+                out.push_str(&format!(" {}:{}", seconds, count));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// lines that can't be read are skipped rather than throwing everything away
+    pub fn deserialize(value: &str) -> Self {
+        let mut statistics = Statistics::default();
+        let mut lines = value.lines();
+        if lines.next() != Some(VERSION) {
+            return statistics;
+        }
+
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            let numbers: Vec<u64> = fields
+                .by_ref()
+                .take(8)
+                .filter_map(|field| field.parse().ok())
+                .collect();
+            let (rows, columns, mines, wins, losses, current, longest, millis) = match numbers[..] {
+                [rows, columns, mines, wins, losses, current, longest, millis] => {
+                    (rows, columns, mines, wins, losses, current, longest, millis)
+                }
+                _ => continue,
+            };
+            let times: Option<BTreeMap<u64, u32>> = fields
+                .map(|pair| {
+                    let (seconds, count) = pair.split_once(':')?;
+                    Some((seconds.parse().ok()?, count.parse().ok()?))
+                })
+                .collect();
+            let times = if let Some(times) = times {
+                times
+            } else {
+                continue;
+            };
+
+            let difficulty = Difficulty {
+                columns: columns as usize,
+                mines: mines as u16,
+                rows: rows as usize,
+            };
+            statistics.records.insert(
+                difficulty,
+                Record {
+                    current_streak: current as u32,
+                    longest_streak: longest as u32,
+                    losses: losses as u32,
+                    times,
+                    total_time: Duration::from_millis(millis),
+                    wins: wins as u32,
+                },
+            );
+        }
+        statistics
+    }
+
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(STORAGE_KEY)
+            .map(|value| Statistics::deserialize(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        storage.save(STORAGE_KEY, &self.serialize())
+    }
+
+    /// forgets every game played so far
+    pub fn reset(&mut self, storage: &mut dyn Storage) -> Result<(), Box<dyn Error>> {
+        self.records.clear();
+        storage.remove(STORAGE_KEY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGINNER: Difficulty = Difficulty {
+        columns: 9,
+        mines: 10,
+        rows: 9,
+    };
+    const EXPERT: Difficulty = Difficulty {
+        columns: 30,
+        mines: 99,
+        rows: 16,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut statistics = Statistics::default();
+        let beginner = statistics.record(BEGINNER);
+        beginner.win(Duration::from_millis(12_500));
+        beginner.lose();
+        beginner.win(Duration::from_millis(9_100));
+        beginner.win(Duration::from_millis(12_900));
+        statistics.record(EXPERT).lose();
+
+        let loaded = Statistics::deserialize(&statistics.serialize());
+        assert_eq!(loaded.records, statistics.records);
+        let beginner = &loaded.records[&BEGINNER];
+        assert_eq!((beginner.wins, beginner.losses), (3, 1));
+        assert_eq!((beginner.current_streak, beginner.longest_streak), (2, 2));
+        assert_eq!(beginner.times.get(&12), Some(&2));
+    }
+
+    #[test]
+    fn broken_lines_are_skipped() {
+        let mut statistics = Statistics::default();
+        statistics.record(BEGINNER).win(Duration::from_secs(10));
+        let serialized = format!(
+            "{}16 30 99 1 2 3\n16 16 40 1 0 1 1 20000 twenty:1\n",
+            statistics.serialize()
+        );
+        assert_eq!(
+            Statistics::deserialize(&serialized).records,
+            statistics.records
+        );
+    }
+
+    #[test]
+    fn other_versions_are_ignored() {
+        let mut statistics = Statistics::default();
+        statistics.record(BEGINNER).lose();
+        let serialized = statistics.serialize().replacen(VERSION, "v0", 1);
+        assert!(Statistics::deserialize(&serialized).records.is_empty());
+    }
+
+    #[test]
+    fn histogram_buckets_cover_every_win() {
+        let mut record = Record::default();
+        for seconds in [10, 11, 19, 30] {
+            record.win(Duration::from_secs(seconds));
+        }
+        assert_eq!(record.histogram(2), vec![(10, 20, 3), (21, 31, 1)]);
+        assert_eq!(record.average_time(), Some(Duration::from_millis(17_500)));
+    }
+}
@@ -0,0 +1,102 @@
+use std::error::Error;
+
+/// Somewhere to keep small pieces of text between sessions, keyed by name.
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>>;
+    /// forgets the key. removing a key that was never saved is not an error
+    fn remove(&mut self, key: &str) -> Result<(), Box<dyn Error>>;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub type PlatformStorage = LocalStorage;
+#[cfg(not(target_arch = "wasm32"))]
+pub type PlatformStorage = FileStorage;
+
+/// the browser's `window.localStorage`. it is looked up on every call since the handle to it
+/// can't be shared between threads
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        LocalStorage::storage()?
+            .get_item(&format!("wasmsweeper.{}", key))
+            .ok()?
+    }
+
+    fn save(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        LocalStorage::storage()
+            .ok_or("localStorage is not available")?
+            .set_item(&format!("wasmsweeper.{}", key), value)
+            .map_err(|_| "localStorage is full or disabled".into())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), Box<dyn Error>> {
+        LocalStorage::storage()
+            .ok_or("localStorage is not available")?
+            .remove_item(&format!("wasmsweeper.{}", key))
+            .map_err(|_| "localStorage is disabled".into())
+    }
+}
+
+/// one file per key in the user's data directory
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    pub dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for FileStorage {
+    fn default() -> Self {
+        use std::env::var_os;
+        use std::path::PathBuf;
+
+        let data_dir = if let Some(dir) = var_os("XDG_DATA_HOME") {
+            PathBuf::from(dir)
+        } else if let Some(dir) = var_os("APPDATA").filter(|_| cfg!(windows)) {
+            PathBuf::from(dir)
+        } else if let Some(home) = var_os("HOME") {
+            if cfg!(target_os = "macos") {
+                PathBuf::from(home).join("Library/Application Support")
+            } else {
+                PathBuf::from(home).join(".local/share")
+            }
+        } else {
+            PathBuf::from(".")
+        };
+
+        FileStorage {
+            dir: data_dir.join("wasmsweeper"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.dir.join(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.dir.join(key), value)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), Box<dyn Error>> {
+        match std::fs::remove_file(self.dir.join(key)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::camera::{window_to_world, MainCamera};
+use crate::components::{
+    board_size, result_contains, ActionEvent, BasicCell, Board, NewGameEvent, ResultSprite,
+};
+use crate::playback::Playback;
+use crate::replay::Action;
+use crate::AppState;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::utils::HashMap;
+
+/// how long in seconds a touch has to be held to flag a cell
+const LONG_PRESS: f64 = 0.5;
+/// touches that move further than this are drags rather than taps
+const TAP_DISTANCE: f32 = 10.0;
+const TOGGLE_WIDTH: f32 = 27.0;
+const TOGGLE_HEIGHT: f32 = 40.0;
+
+#[derive(Default)]
+pub struct TouchState {
+    /// taps flag cells rather than opening them
+    pub flag_mode: bool,
+    /// when each touch that is still down started, and whether it has already flagged its cell
+    presses: HashMap<u64, (f64, bool)>,
+}
+
+/// switches taps between opening and flagging. only shown once the screen has been touched
+#[derive(Component)]
+pub struct FlagModeToggle;
+
+/// bevy only measures touches from the bottom of the window, like the cursor, on android and ios.
+/// everywhere else they come from the top, including the ones read from the canvas on the web
+fn touch_position(window: &Window, position: Vec2) -> Vec2 {
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        position
+    } else {
+        Vec2::new(position.x, window.height() - position.y)
+    }
+}
+
+fn cell_at(cell_query: &Query<&BasicCell>, position: Vec2) -> Option<(usize, usize)> {
+    cell_query
+        .iter()
+        .find(|basic_cell| basic_cell.contains(position))
+        .map(|basic_cell| (basic_cell.row, basic_cell.column))
+}
+
+pub fn spawn_flag_mode_toggle(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(TOGGLE_WIDTH, TOGGLE_HEIGHT)),
+                ..Default::default()
+            },
+            texture: asset_server.load("flag.png"),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(FlagModeToggle);
+}
+
+/// keeps the toggle just below the board and dims it while taps open cells
+pub fn update_flag_mode_toggle(
+    board_query: Query<&Board>,
+    touch_state: Res<TouchState>,
+    mut toggle_query: Query<(&mut Sprite, &mut Transform), With<FlagModeToggle>>,
+) {
+    let board = board_query.single();
+    let (mut sprite, mut transform) = toggle_query.single_mut();
+
+    let extent = board_size(board.game.height, board.game.width);
+    transform.translation = Vec3::new(0.0, -(extent.y / 2.0 + 20.0 + TOGGLE_HEIGHT / 2.0), 1.0);
+    sprite.color = if touch_state.flag_mode {
+        Color::WHITE
+    } else {
+        Color::rgba(1.0, 1.0, 1.0, 0.35)
+    };
+}
+
+/// taps on the win or lose sprite start a new game. the browser's made up click for the tap is
+/// thrown away on the web, so the mouse handlers don't see it
+#[allow(clippy::too_many_arguments)]
+pub fn touch_cells(
+    board_query: Query<&Board>,
+    cell_query: Query<&BasicCell>,
+    mut ev_action: EventWriter<ActionEvent>,
+    mut ev_new_game: EventWriter<NewGameEvent>,
+    images: Res<Assets<Image>>,
+    playback: Res<Playback>,
+    time: Res<Time>,
+    result_query: Query<(&Transform, &Handle<Image>), With<ResultSprite>>,
+    mut toggle_query: Query<(&Transform, &mut Visibility), With<FlagModeToggle>>,
+    touches: Res<Touches>,
+    mut touch_state: ResMut<TouchState>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let now = time.seconds_since_startup();
+    for touch in touches.iter_just_pressed() {
+        touch_state.presses.insert(touch.id(), (now, false));
+    }
+
+    let (window, (camera, camera_transform)) =
+        if let (Some(window), Some(camera)) = (windows.get_primary(), camera_query.iter().next()) {
+            (window, camera)
+        } else {
+            return;
+        };
+    let to_world = |position: Vec2| {
+        window_to_world(
+            window,
+            camera,
+            camera_transform,
+            touch_position(window, position),
+        )
+    };
+    let board = board_query.single();
+    let (toggle_transform, mut toggle_visibility) = toggle_query.single_mut();
+    if touches.iter().next().is_some() {
+        toggle_visibility.is_visible = true;
+    }
+    if playback.is_active() {
+        return;
+    }
+
+    let flag = |position: (usize, usize)| {
+        if board.game.cells[position.0][position.1].opened {
+            Action::AutoFlag(position)
+        } else {
+            Action::ToggleFlag(position)
+        }
+    };
+
+    // long presses flag as soon as they have been held long enough, without waiting for release
+    for touch in touches.iter() {
+        let (start, flagged) = if let Some(press) = touch_state.presses.get_mut(&touch.id()) {
+            press
+        } else {
+            continue;
+        };
+        if *flagged || now - *start < LONG_PRESS || touch.distance().length() > TAP_DISTANCE {
+            continue;
+        }
+        *flagged = true;
+
+        let position = to_world(touch.position());
+        if board.game.is_over() {
+            continue;
+        }
+        if let Some(position) = cell_at(&cell_query, position) {
+            ev_action.send(ActionEvent(flag(position)));
+        }
+    }
+
+    for touch in touches.iter_just_released() {
+        let (_, flagged) = touch_state
+            .presses
+            .remove(&touch.id())
+            .unwrap_or((now, false));
+        if flagged || touch.distance().length() > TAP_DISTANCE {
+            continue;
+        }
+
+        let position = to_world(touch.position());
+        let offset = (position - toggle_transform.translation.truncate()).abs();
+        if offset.x <= TOGGLE_WIDTH / 2.0 && offset.y <= TOGGLE_HEIGHT / 2.0 {
+            touch_state.flag_mode = !touch_state.flag_mode;
+            continue;
+        }
+        if result_contains(&images, &result_query, position) {
+            ev_new_game.send(NewGameEvent);
+            continue;
+        }
+
+        if board.game.is_over() {
+            continue;
+        }
+        let position = if let Some(position) = cell_at(&cell_query, position) {
+            position
+        } else {
+            continue;
+        };
+        if touch_state.flag_mode {
+            ev_action.send(ActionEvent(flag(position)));
+        } else if board.game.cells[position.0][position.1].opened {
+            ev_action.send(ActionEvent(Action::Chord(position)));
+        } else {
+            ev_action.send(ActionEvent(Action::Reveal(position)));
+        }
+    }
+}
+
+pub struct InGameTouchPlugin;
+
+impl Plugin for InGameTouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchState>();
+        app.add_startup_system(spawn_flag_mode_toggle);
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(touch_cells.label("touch"))
+                .with_system(update_flag_mode_toggle.after("touch")),
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+mod in_game_touch_handlers;
+use in_game_touch_handlers::InGameTouchPlugin;
+#[cfg(target_arch = "wasm32")]
+mod web_touches;
+#[cfg(target_arch = "wasm32")]
+use web_touches::WebTouchPlugin;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InGameTouchPlugin);
+        #[cfg(target_arch = "wasm32")]
+        app.add_plugin(WebTouchPlugin);
+    }
+}
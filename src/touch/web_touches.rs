@@ -0,0 +1,118 @@
+//! winit doesn't pass touches on from the browser, only the pointer events a browser also sends
+//! for them, which reach bevy as left clicks. the touches are read straight from the canvas here
+//! and handed to bevy as `TouchInput` events, the same way winit reports them everywhere else.
+
+use crate::AppState;
+
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{AddEventListenerOptions, Element, PointerEvent, TouchEvent};
+
+/// touches seen by the listeners since the last frame
+static TOUCHES: Mutex<Vec<TouchInput>> = Mutex::new(Vec::new());
+/// the last pointer to be pressed on the canvas was a finger rather than a mouse
+static TOUCH_POINTER: AtomicBool = AtomicBool::new(false);
+
+fn listen(canvas: &Element, event: &str, phase: TouchPhase) {
+    let listener_canvas = canvas.clone();
+    let listener = Closure::wrap(Box::new(move |event: TouchEvent| {
+        // stops the browser scrolling or zooming the page, and sending mouse events for the touch
+        event.prevent_default();
+        let rect = listener_canvas.get_bounding_client_rect();
+        let changed = event.changed_touches();
+        let mut touches = TOUCHES.lock().unwrap();
+        for index in 0..changed.length() {
+            if let Some(touch) = changed.get(index) {
+                // from the top left of the canvas, like winit's touches on desktop
+                touches.push(TouchInput {
+                    force: None,
+                    id: touch.identifier() as u32 as u64,
+                    phase,
+                    position: Vec2::new(
+                        (touch.client_x() as f64 - rect.left()) as f32,
+                        (touch.client_y() as f64 - rect.top()) as f32,
+                    ),
+                });
+            }
+        }
+    }) as Box<dyn FnMut(TouchEvent)>);
+    let mut options = AddEventListenerOptions::new();
+    options.passive(false);
+    if canvas
+        .add_event_listener_with_callback_and_add_event_listener_options(
+            event,
+            listener.as_ref().unchecked_ref(),
+            &options,
+        )
+        .is_err()
+    {
+        warn!("couldn't listen for {} on the canvas", event);
+    }
+    listener.forget();
+}
+
+/// adds the listeners to the canvas once winit has put it on the page
+fn listen_for_touches(mut listening: Local<bool>) {
+    if *listening {
+        return;
+    }
+    let canvas = if let Some(Ok(Some(canvas))) = web_sys::window()
+        .and_then(|window| window.document())
+        .map(|document| document.query_selector("canvas"))
+    {
+        canvas
+    } else {
+        return;
+    };
+    *listening = true;
+
+    listen(&canvas, "touchstart", TouchPhase::Started);
+    listen(&canvas, "touchmove", TouchPhase::Moved);
+    listen(&canvas, "touchend", TouchPhase::Ended);
+    listen(&canvas, "touchcancel", TouchPhase::Cancelled);
+
+    let pointer_listener = Closure::wrap(Box::new(move |event: PointerEvent| {
+        TOUCH_POINTER.store(event.pointer_type() == "touch", Ordering::Relaxed);
+    }) as Box<dyn FnMut(PointerEvent)>);
+    if canvas
+        .add_event_listener_with_callback("pointerdown", pointer_listener.as_ref().unchecked_ref())
+        .is_err()
+    {
+        warn!("couldn't listen for pointerdown on the canvas");
+    }
+    pointer_listener.forget();
+}
+
+fn forward_touches(mut ev_touch: EventWriter<TouchInput>) {
+    for touch in TOUCHES.lock().unwrap().drain(..) {
+        ev_touch.send(touch);
+    }
+}
+
+/// the left click the browser makes up for a tap is used up, since the touch handlers already
+/// act on the tap itself
+fn ignore_touch_clicks(mut mouse_button_input: ResMut<Input<MouseButton>>) {
+    if TOUCH_POINTER.load(Ordering::Relaxed) && mouse_button_input.just_pressed(MouseButton::Left) {
+        mouse_button_input.reset(MouseButton::Left);
+    }
+}
+
+pub struct WebTouchPlugin;
+
+impl Plugin for WebTouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(listen_for_touches);
+        // before bevy turns the events into `Touches` in the pre-update stage
+        app.add_system_to_stage(CoreStage::First, forward_touches);
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame).with_system(
+                ignore_touch_clicks
+                    .after("skip_animations")
+                    .before("both_buttons"),
+            ),
+        );
+    }
+}